@@ -4,7 +4,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_client(false)
         .build_server(true)
-        .type_attribute(".", "#[derive(Eq)]")
+        // Eq can't be derived for every message since some contain floating point numbers
+        .type_attribute(".", "#[allow(clippy::derive_partial_eq_without_eq)]")
         .compile(&["./lights.proto"], &["."])?;
 
     Ok(())
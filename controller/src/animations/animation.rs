@@ -1,4 +1,7 @@
-use super::{instance, BuildError, LoadError, SaveError};
+use super::{
+    instance::{self, Geometry},
+    BuildError, LoadError, Parameters, SaveError,
+};
 use crate::pixels::Pixels;
 use std::{
     io::{self, ErrorKind},
@@ -9,7 +12,10 @@ use tracing::{debug, instrument};
 use wasmer::{CompilerConfig, Dylib, ExportError, Instance, Module, NativeFunc, Store};
 
 /// An animation to be run by the animator
-pub(crate) struct Animation(Instance);
+pub(crate) struct Animation {
+    instance: Instance,
+    parameters: Parameters,
+}
 
 impl Animation {
     /// Load and compile an animation from bytes
@@ -18,28 +24,35 @@ impl Animation {
         wasm: B,
         development: bool,
         pixels: Pixels,
+        geometry: Geometry,
     ) -> Result<Self, BuildError> {
         let engine = Dylib::new(get_compiler(development)).engine();
         let store = Store::new(&engine);
         let module = Module::new(&store, wasm)?;
         debug!("loaded module");
 
-        let instance = instance::build(module, store, pixels)?;
+        let parameters = Parameters::default();
+        let instance = instance::build(module, store, pixels, parameters.clone(), geometry)?;
         debug!("built instance");
 
         // Ensure the exported function exists and has the correct signature
-        let animation = Self(instance);
+        let animation = Self {
+            instance,
+            parameters,
+        };
         animation.animate()?;
 
         Ok(animation)
     }
 
-    /// Load a pre-compiled animation from disk
-    #[instrument(skip(base, pixels))]
+    /// Load a pre-compiled animation from disk, running it with the given parameters
+    #[instrument(skip(base, pixels, parameters, geometry))]
     pub async fn load<P: AsRef<Path>>(
         id: &str,
         base: P,
         pixels: Pixels,
+        parameters: Parameters,
+        geometry: Geometry,
     ) -> Result<Self, LoadError> {
         // Read the animation
         let path = base.as_ref().join(id);
@@ -53,10 +66,13 @@ impl Animation {
         let module = unsafe { Module::deserialize(&store, &wasm)? };
         debug!("loaded animation");
 
-        let instance = instance::build(module, store, pixels)?;
+        let instance = instance::build(module, store, pixels, parameters.clone(), geometry)?;
         debug!("built instance");
 
-        Ok(Self(instance))
+        Ok(Self {
+            instance,
+            parameters,
+        })
     }
 
     /// Delete an animation from disk
@@ -76,7 +92,7 @@ impl Animation {
     /// Save an animation to a file
     #[instrument(skip(self, base))]
     pub async fn save<P: AsRef<Path>>(&self, id: &str, base: P) -> Result<(), SaveError> {
-        let serialized = self.0.module().serialize()?;
+        let serialized = self.instance.module().serialize()?;
 
         let path = base.as_ref().join(id);
         fs::write(path, &serialized).await?;
@@ -84,9 +100,14 @@ impl Animation {
         Ok(())
     }
 
+    /// The parameters the animation is running with
+    pub fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
     /// Get the animate method to call
     pub fn animate(&self) -> Result<NativeFunc<(), ()>, ExportError> {
-        self.0.exports.get_native_function("animate")
+        self.instance.exports.get_native_function("animate")
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::errors::{StateError, UnknownSegment};
 use std::io::{self, ErrorKind};
 use thiserror::Error;
 use wasmer::{CompileError, DeserializeError, ExportError, InstantiationError, SerializeError};
@@ -12,6 +13,8 @@ pub enum BuildError {
     InvalidSignature,
     #[error("missing animate function")]
     MethodNotFound,
+    #[error("invalid metadata: {0}")]
+    Metadata(#[from] MetadataError),
 }
 
 impl From<ExportError> for BuildError {
@@ -50,6 +53,20 @@ pub enum RegistrationError {
     BuildError(#[from] BuildError),
     #[error("failed to save animation: {0}")]
     SaveError(#[from] SaveError),
+    #[error("failed to save metadata: {0}")]
+    Metadata(#[from] StateError),
+}
+
+#[derive(Debug, Error)]
+pub enum MetadataError {
+    #[error("failed to parse metadata: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("metadata can only be declared once")]
+    Duplicate,
+    #[error("{0}")]
+    Invalid(&'static str),
+    #[error("invalid parameter {0:?}: {1}")]
+    InvalidParameter(String, &'static str),
 }
 
 #[derive(Debug, Error)]
@@ -59,3 +76,23 @@ pub enum SaveError {
     #[error("failed to serialize animation: {0}")]
     Serialization(#[from] SerializeError),
 }
+
+#[derive(Debug, Error)]
+pub enum ParameterError {
+    #[error(transparent)]
+    UnknownSegment(#[from] UnknownSegment),
+    #[error("no animation is running")]
+    NotRunning,
+    #[error("unknown parameter {0:?}")]
+    Unknown(String),
+    #[error("invalid parameter {0:?}: {1}")]
+    Invalid(String, &'static str),
+}
+
+#[derive(Debug, Error)]
+pub enum PlayError {
+    #[error(transparent)]
+    UnknownSegment(#[from] UnknownSegment),
+    #[error("unknown playlist {0:?}")]
+    UnknownPlaylist(String),
+}
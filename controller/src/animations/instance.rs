@@ -1,8 +1,45 @@
-use crate::pixels::Pixels;
+use super::parameters::{Parameters, Value};
+use crate::{
+    config::Config,
+    pixels::{Color, Pixels},
+};
 use std::thread;
 use std::time::Duration;
 use tracing::instrument;
-use wasmer::{imports, Function, FunctionType, Instance, InstantiationError, Module, Store, Type};
+use wasmer::{
+    imports, Array, Function, FunctionType, Instance, InstantiationError, LazyInit, Memory, Module,
+    RuntimeError, Store, Type, Val, WasmPtr, WasmerEnv,
+};
+
+/// The physical layout of the strip, exposed to animations so they don't need to hard-code it
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Geometry {
+    /// The total number of LEDs on the strip
+    pub leds: u16,
+    /// The number of LEDs per meter
+    pub density: u16,
+    /// The length of the strip in meters
+    pub length: u16,
+}
+
+impl Geometry {
+    pub fn new(config: &Config) -> Self {
+        Geometry {
+            leds: config.leds,
+            density: config.strip_density,
+            length: config.strip_length,
+        }
+    }
+}
+
+/// Gives the methods for reading parameters access to the animation's memory, where names are
+/// passed as a pointer and length
+#[derive(Clone, WasmerEnv)]
+struct Env {
+    parameters: Parameters,
+    #[wasmer(export(optional = true))]
+    memory: LazyInit<Memory>,
+}
 
 /// Build a new instance with its attached methods
 #[instrument(skip_all)]
@@ -10,11 +47,34 @@ pub(crate) fn build(
     module: Module,
     store: Store,
     pixels: Pixels,
+    parameters: Parameters,
+    geometry: Geometry,
 ) -> Result<Instance, InstantiationError> {
     // Create a bunch of references to pixels to be used by the closures
     let brightness_pixels = pixels.clone();
     let fill_pixels = pixels.clone();
+    let fill_rgbw_pixels = pixels.clone();
+    let fill16_pixels = pixels.clone();
+    let fill_rgbw16_pixels = pixels.clone();
     let set_pixels = pixels.clone();
+    let set_rgbw_pixels = pixels.clone();
+    let set16_pixels = pixels.clone();
+    let set_rgbw16_pixels = pixels.clone();
+    let env = Env {
+        parameters,
+        memory: LazyInit::new(),
+    };
+
+    // Animations on a segment draw relative to its start, so they only need to know its size to
+    // fill it, but can use its bounds to line up with the rest of the strip
+    let bounds = pixels.bounds();
+    let constant = |value: u16| {
+        Function::new(
+            &store,
+            &FunctionType::new(Vec::new(), vec![Type::I32]),
+            move |_| Ok(vec![Val::I32(value as i32)]),
+        )
+    };
 
     // Build all the methods to be exposed
     let imports = imports! {
@@ -30,7 +90,33 @@ pub(crate) fn build(
                 let g = u8_from_value(&args[1])?;
                 let b = u8_from_value(&args[2])?;
 
-                fill_pixels.fill(r, g, b);
+                fill_pixels.fill(Color::rgb(r, g, b));
+                Ok(Vec::new())
+            }),
+            "fill_rgbw" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let r = u8_from_value(&args[0])?;
+                let g = u8_from_value(&args[1])?;
+                let b = u8_from_value(&args[2])?;
+                let w = u8_from_value(&args[3])?;
+
+                fill_rgbw_pixels.fill(Color::rgbw(r, g, b, w));
+                Ok(Vec::new())
+            }),
+            "fill16" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let r = u16_from_value(&args[0])?;
+                let g = u16_from_value(&args[1])?;
+                let b = u16_from_value(&args[2])?;
+
+                fill16_pixels.fill(Color::rgb16(r, g, b));
+                Ok(Vec::new())
+            }),
+            "fill_rgbw16" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let r = u16_from_value(&args[0])?;
+                let g = u16_from_value(&args[1])?;
+                let b = u16_from_value(&args[2])?;
+                let w = u16_from_value(&args[3])?;
+
+                fill_rgbw16_pixels.fill(Color::rgbw16(r, g, b, w));
                 Ok(Vec::new())
             }),
             "set" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
@@ -39,7 +125,36 @@ pub(crate) fn build(
                 let g = u8_from_value(&args[2])?;
                 let b = u8_from_value(&args[3])?;
 
-                set_pixels.set(index, r, g, b);
+                set_pixels.set(index, Color::rgb(r, g, b));
+                Ok(Vec::new())
+            }),
+            "set_rgbw" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let index = u16_from_value(&args[0])?;
+                let r = u8_from_value(&args[1])?;
+                let g = u8_from_value(&args[2])?;
+                let b = u8_from_value(&args[3])?;
+                let w = u8_from_value(&args[4])?;
+
+                set_rgbw_pixels.set(index, Color::rgbw(r, g, b, w));
+                Ok(Vec::new())
+            }),
+            "set16" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let index = u16_from_value(&args[0])?;
+                let r = u16_from_value(&args[1])?;
+                let g = u16_from_value(&args[2])?;
+                let b = u16_from_value(&args[3])?;
+
+                set16_pixels.set(index, Color::rgb16(r, g, b));
+                Ok(Vec::new())
+            }),
+            "set_rgbw16" => Function::new(&store, &FunctionType::new(vec![Type::I32, Type::I32, Type::I32, Type::I32, Type::I32], Vec::new()), move |args| {
+                let index = u16_from_value(&args[0])?;
+                let r = u16_from_value(&args[1])?;
+                let g = u16_from_value(&args[2])?;
+                let b = u16_from_value(&args[3])?;
+                let w = u16_from_value(&args[4])?;
+
+                set_rgbw16_pixels.set(index, Color::rgbw16(r, g, b, w));
                 Ok(Vec::new())
             }),
             "show" => Function::new(&store, &FunctionType::new(Vec::new(), Vec::new()), move |_| {
@@ -47,6 +162,16 @@ pub(crate) fn build(
                 Ok(Vec::new())
             }),
             "sleep" => Function::new_native(&store, sleep),
+            "param_f64" => Function::new_native_with_env(&store, env.clone(), param_f64),
+            "param_bool" => Function::new_native_with_env(&store, env.clone(), param_bool),
+            "param_color" => Function::new_native_with_env(&store, env.clone(), param_color),
+            "param_string" => Function::new_native_with_env(&store, env, param_string),
+            "led_count" => constant(bounds.end - bounds.start),
+            "segment_start" => constant(bounds.start),
+            "segment_end" => constant(bounds.end),
+            "strip_leds" => constant(geometry.leds),
+            "strip_density" => constant(geometry.density),
+            "strip_length" => constant(geometry.length),
         }
     };
 
@@ -59,6 +184,72 @@ fn sleep(seconds: f64) {
     thread::sleep(duration);
 }
 
+impl Env {
+    /// Look up the value of a parameter by the name the animation passed
+    fn parameter(&self, name: WasmPtr<u8, Array>, length: u32) -> Result<Value, RuntimeError> {
+        let memory = self
+            .memory_ref()
+            .ok_or_else(|| RuntimeError::new("animation must export its memory"))?;
+        let name = name
+            .get_utf8_string(memory, length)
+            .ok_or_else(|| RuntimeError::new("invalid parameter name"))?;
+
+        self.parameters
+            .get(&name)
+            .ok_or_else(|| RuntimeError::new(format!("unknown parameter {:?}", name)))
+    }
+}
+
+/// Get the value of a number parameter
+fn param_f64(env: &Env, name: WasmPtr<u8, Array>, length: u32) -> Result<f64, RuntimeError> {
+    match env.parameter(name, length)? {
+        Value::Number(n) => Ok(n),
+        _ => Err(RuntimeError::new("parameter is not a number")),
+    }
+}
+
+/// Get the value of a boolean parameter as 1 or 0
+fn param_bool(env: &Env, name: WasmPtr<u8, Array>, length: u32) -> Result<u32, RuntimeError> {
+    match env.parameter(name, length)? {
+        Value::Boolean(b) => Ok(b as u32),
+        _ => Err(RuntimeError::new("parameter is not a boolean")),
+    }
+}
+
+/// Get the value of a color parameter packed as `0xWWRRGGBB`
+fn param_color(env: &Env, name: WasmPtr<u8, Array>, length: u32) -> Result<u32, RuntimeError> {
+    match env.parameter(name, length)? {
+        Value::Color(c) => Ok(u32::from_be_bytes([c.w.unwrap_or_default(), c.r, c.g, c.b])),
+        _ => Err(RuntimeError::new("parameter is not a color")),
+    }
+}
+
+/// Copy the value of a string parameter into a buffer, returning its full length in bytes. Values
+/// longer than the buffer are cut off.
+fn param_string(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    length: u32,
+    buffer: WasmPtr<u8, Array>,
+    capacity: u32,
+) -> Result<u32, RuntimeError> {
+    let value = match env.parameter(name, length)? {
+        Value::String(s) => s,
+        _ => return Err(RuntimeError::new("parameter is not a string")),
+    };
+
+    let written = value.len().min(capacity as usize);
+    let memory = env.memory_ref().unwrap();
+    let cells = buffer
+        .deref(memory, 0, written as u32)
+        .ok_or_else(|| RuntimeError::new("invalid buffer"))?;
+    for (cell, byte) in cells.iter().zip(value.bytes()) {
+        cell.set(byte);
+    }
+
+    Ok(value.len() as u32)
+}
+
 macro_rules! int_from_value {
     ($name:ident, $result:ty) => {
         fn $name(v: &wasmer::Val) -> Result<$result, wasmer::RuntimeError> {
@@ -79,3 +270,53 @@ macro_rules! int_from_value {
 
 int_from_value!(u8_from_value, u8);
 int_from_value!(u16_from_value, u16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animations::parameters::Values;
+    use wasmer::{Dylib, MemoryType};
+
+    /// The offsets of the name and buffer in memory
+    const NAME: u32 = 0;
+    const BUFFER: u32 = 64;
+
+    /// Read a string parameter named `label` into a buffer of some capacity, returning the
+    /// length reported and the contents of the buffer
+    fn read(value: &str, capacity: u32) -> (u32, Vec<u8>) {
+        let store = Store::new(&Dylib::headless().engine());
+        let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+        let view = memory.view::<u8>();
+        for (cell, byte) in view[NAME as usize..].iter().zip(b"label") {
+            cell.set(*byte);
+        }
+
+        let values = Values::from([("label".to_string(), Value::String(value.to_string()))]);
+        let mut env = Env {
+            parameters: Parameters::new(&Default::default(), values),
+            memory: LazyInit::new(),
+        };
+        env.memory.initialize(memory.clone());
+
+        let length = param_string(&env, WasmPtr::new(NAME), 5, WasmPtr::new(BUFFER), capacity);
+        let buffer = view[BUFFER as usize..(BUFFER + 16) as usize]
+            .iter()
+            .map(|cell| cell.get())
+            .collect();
+        (length.unwrap(), buffer)
+    }
+
+    #[test]
+    fn param_string_fits() {
+        let (length, buffer) = read("steps", 16);
+        assert_eq!(length, 5);
+        assert_eq!(&buffer[..6], b"steps\0");
+    }
+
+    #[test]
+    fn param_string_truncates() {
+        let (length, buffer) = read("a longer value", 4);
+        assert_eq!(length, 14);
+        assert_eq!(&buffer[..5], b"a lo\0");
+    }
+}
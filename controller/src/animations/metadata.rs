@@ -0,0 +1,343 @@
+use super::MetadataError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The name of the custom section animations embed their metadata in
+pub const SECTION: &str = "lights";
+
+/// What an animation declares about itself. Animations embed this as TOML in a custom section of
+/// their module named `lights`, and everything in it is optional.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metadata {
+    /// A human-readable name for the animation
+    pub name: Option<String>,
+    /// Who wrote the animation
+    pub author: Option<String>,
+    /// What the animation looks like
+    pub description: Option<String>,
+    /// The version of the animation
+    pub version: Option<String>,
+    /// The number of LEDs the animation was designed for
+    pub leds: Option<u16>,
+    /// The parameters the animation can be customized with, by name
+    pub parameters: BTreeMap<String, Parameter>,
+}
+
+/// A value an animation can be customized with
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Parameter {
+    /// What the parameter changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The type of value and the value used when none is given
+    #[serde(flatten)]
+    pub kind: ParameterKind,
+}
+
+/// The types of values parameters can hold
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ParameterKind {
+    /// A number, optionally limited to a range
+    Number {
+        default: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    /// A color with each channel in the range 0-255 inclusive
+    Color { default: ParameterColor },
+    /// Either on or off
+    Boolean { default: bool },
+    /// Some text, optionally limited to a set of options
+    String {
+        default: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        options: Vec<String>,
+    },
+}
+
+/// A color with each channel in the range 0-255 inclusive. The white channel is only used by RGBW
+/// strips.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ParameterColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub w: Option<u8>,
+}
+
+impl Metadata {
+    /// Read the metadata embedded in a module, if it has any. Wasmer only keeps the last custom
+    /// section with each name, so the sections are read from the module's bytes instead.
+    pub fn from_wasm(wasm: &[u8]) -> Result<Self, MetadataError> {
+        let sections =
+            custom_sections(wasm, SECTION).ok_or(MetadataError::Invalid("malformed module"))?;
+        let metadata = match sections.as_slice() {
+            [] => return Ok(Metadata::default()),
+            [section] => toml::from_slice::<Metadata>(section)?,
+            _ => return Err(MetadataError::Duplicate),
+        };
+
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    /// Ensure the declared values make sense
+    fn validate(&self) -> Result<(), MetadataError> {
+        if self.leds == Some(0) {
+            return Err(MetadataError::Invalid("leds must be greater than 0"));
+        }
+
+        for (name, parameter) in &self.parameters {
+            let invalid = |reason| Err(MetadataError::InvalidParameter(name.to_owned(), reason));
+
+            let valid_name = name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if name.is_empty() || !valid_name {
+                return invalid("names may only contain letters, numbers, dashes, and underscores");
+            }
+
+            match &parameter.kind {
+                ParameterKind::Number { default, min, max } => {
+                    if ![Some(*default), *min, *max]
+                        .iter()
+                        .flatten()
+                        .all(|n| n.is_finite())
+                    {
+                        return invalid("numbers must be finite");
+                    }
+                    if let (Some(min), Some(max)) = (min, max) {
+                        if min > max {
+                            return invalid("min must not be greater than max");
+                        }
+                    }
+                    if matches!(min, Some(min) if default < min)
+                        || matches!(max, Some(max) if default > max)
+                    {
+                        return invalid("default must be between min and max");
+                    }
+                }
+                ParameterKind::String { default, options } => {
+                    if !options.is_empty() && !options.contains(default) {
+                        return invalid("default must be one of the options");
+                    }
+                }
+                ParameterKind::Color { .. } | ParameterKind::Boolean { .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Find the contents of every custom section with a name, in the order they appear in a module
+fn custom_sections<'a>(wasm: &'a [u8], name: &str) -> Option<Vec<&'a [u8]>> {
+    // Skip the magic number and version
+    let mut rest = wasm.get(8..)?;
+    let mut found = Vec::new();
+
+    while let Some((&id, after)) = rest.split_first() {
+        let (size, after) = leb128(after)?;
+        let contents = after.get(..size)?;
+        rest = &after[size..];
+
+        if id == 0 {
+            let (length, contents) = leb128(contents)?;
+            if contents.get(..length)? == name.as_bytes() {
+                found.push(&contents[length..]);
+            }
+        }
+    }
+
+    Some(found)
+}
+
+/// Decode an unsigned 32-bit LEB128 integer, returning it along with the bytes that follow
+fn leb128(data: &[u8]) -> Option<(usize, &[u8])> {
+    let mut value = 0;
+    for (i, byte) in data.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &data[i + 1..]));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a module with no code, only a custom section for each of the metadata given. Another
+    /// custom section comes first to check that it's skipped.
+    fn module(sections: &[&str]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        let other = ("name", "\0\x07rainbow");
+        for (name, contents) in [other]
+            .into_iter()
+            .chain(sections.iter().map(|s| (SECTION, *s)))
+        {
+            let mut section = Vec::new();
+            encode(&mut section, name.len());
+            section.extend_from_slice(name.as_bytes());
+            section.extend_from_slice(contents.as_bytes());
+
+            wasm.push(0);
+            encode(&mut wasm, section.len());
+            wasm.extend(section);
+        }
+
+        wasm
+    }
+
+    /// Append an unsigned LEB128 encoded integer
+    fn encode(data: &mut Vec<u8>, mut value: usize) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+    }
+
+    /// Parse and validate metadata, returning the reason it's invalid
+    fn invalid(contents: &str) -> String {
+        let metadata = toml::from_str::<Metadata>(contents).unwrap();
+        match metadata.validate() {
+            Ok(()) => panic!("{:?} should be invalid", contents),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    const RAINBOW: &str = r#"
+        name = "Rainbow"
+        leds = 60
+
+        [parameters.speed]
+        type = "number"
+        description = "How far the rainbow moves each frame"
+        default = 1.0
+        min = 1.0
+        max = 32.0
+
+        [parameters.tint]
+        type = "color"
+        default = { r = 255, g = 128, b = 0, w = 10 }
+
+        [parameters.reverse]
+        type = "boolean"
+        default = false
+
+        [parameters.mode]
+        type = "string"
+        default = "smooth"
+        options = ["smooth", "steps"]
+    "#;
+
+    #[test]
+    fn reads_section() {
+        let metadata = Metadata::from_wasm(&module(&[RAINBOW])).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("Rainbow"));
+        assert_eq!(metadata.leds, Some(60));
+        assert_eq!(metadata.parameters.len(), 4);
+        assert_eq!(
+            metadata.parameters["speed"].kind,
+            ParameterKind::Number {
+                default: 1.0,
+                min: Some(1.0),
+                max: Some(32.0)
+            }
+        );
+        assert_eq!(
+            metadata.parameters["tint"].kind,
+            ParameterKind::Color {
+                default: ParameterColor {
+                    r: 255,
+                    g: 128,
+                    b: 0,
+                    w: Some(10)
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn missing_section_is_empty() {
+        let metadata = Metadata::from_wasm(&module(&[])).unwrap();
+        assert_eq!(metadata, Metadata::default());
+    }
+
+    #[test]
+    fn rejects_duplicate_sections() {
+        let result = Metadata::from_wasm(&module(&[RAINBOW, RAINBOW]));
+        assert!(matches!(result, Err(MetadataError::Duplicate)));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for contents in [
+            "name = 1",
+            "unknown = true",
+            "[parameters.speed]\ntype = \"number\"",
+            "[parameters.speed]\ntype = \"integer\"\ndefault = 1",
+            "[parameters.tint]\ntype = \"color\"\ndefault = { r = 256, g = 0, b = 0 }",
+        ] {
+            let result = Metadata::from_wasm(&module(&[contents]));
+            assert!(
+                matches!(result, Err(MetadataError::Parse(_))),
+                "{:?}",
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn validates_values() {
+        assert_eq!(invalid("leds = 0"), "leds must be greater than 0");
+
+        for name in ["\"with space\"", "\"\"", "\"ünicode\"", "\"dot.ted\""] {
+            let contents = format!("[parameters.{}]\ntype = \"boolean\"\ndefault = true", name);
+            assert!(
+                invalid(&contents).contains("names may only contain"),
+                "{}",
+                name
+            );
+        }
+
+        let number = |fields: &str| format!("[parameters.n]\ntype = \"number\"\n{}", fields);
+        for fields in [
+            "default = nan",
+            "default = inf",
+            "default = 0.0\nmin = -inf",
+        ] {
+            assert!(invalid(&number(fields)).contains("numbers must be finite"));
+        }
+        assert!(invalid(&number("default = 1.0\nmin = 2.0\nmax = 1.0")).contains("min must not"));
+        assert!(invalid(&number("default = 5.0\nmax = 4.0")).contains("between min and max"));
+        assert!(invalid(&number("default = 0.0\nmin = 1.0")).contains("between min and max"));
+
+        let string = "[parameters.s]\ntype = \"string\"\ndefault = \"c\"\noptions = [\"a\", \"b\"]";
+        assert!(invalid(string).contains("one of the options"));
+    }
+
+    #[test]
+    fn accepts_edge_values() {
+        for contents in [
+            "",
+            "[parameters.n]\ntype = \"number\"\ndefault = 1.0\nmin = 1.0\nmax = 1.0",
+            "[parameters.dashed-name_2]\ntype = \"string\"\ndefault = \"anything\"",
+        ] {
+            let metadata = toml::from_str::<Metadata>(contents).unwrap();
+            assert!(metadata.validate().is_ok(), "{:?}", contents);
+        }
+    }
+}
@@ -1,153 +1,685 @@
-use crate::pixels::Pixels;
-use std::{io, path::PathBuf, sync::Arc};
+use crate::{
+    config::Config,
+    errors::{StateError, UnknownSegment},
+    pixels::{LayerId, Pixels},
+    state,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::{
-    sync::mpsc::{self, error::TryRecvError, Receiver, Sender},
+    fs,
+    sync::{
+        mpsc::{self, error::TryRecvError, Receiver, Sender},
+        watch, Mutex,
+    },
     task::{self, JoinHandle},
+    time,
 };
-use tracing::{error, info, instrument};
+use tracing::{error, info, info_span, instrument, Instrument};
 
 mod animation;
 mod error;
 mod instance;
+mod metadata;
+mod parameters;
+mod playlist;
 
 use animation::Animation;
-pub use error::{BuildError, LoadError, RegistrationError, SaveError};
+pub use error::{
+    BuildError, LoadError, MetadataError, ParameterError, PlayError, RegistrationError, SaveError,
+};
+use instance::Geometry;
+pub use metadata::{Metadata, ParameterColor, ParameterKind};
+use parameters::Parameters;
+pub use parameters::{Value, Values};
+use playlist::Queue;
+pub use playlist::{Entry, Playlist, Position};
 
 /// The action for the executor to perform
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum Action {
-    /// Start the animation with the specified id
-    Start(String),
-    /// Stop any currently running animation
-    Stop,
+    /// Start the animation with the specified id, customized by the given parameters
+    Start(String, Values),
+    /// Change the parameters of the running animation, as long as it has the specified id
+    Configure(String, Values),
+    /// Play the entries of a playlist, identified by its name
+    Play(String, Playlist),
+    /// Stop any currently running animation or playlist. The sender is dropped once the
+    /// animation has been cleared, so callers can wait for that to happen.
+    Stop(Option<Sender<()>>),
+    /// Stop running frames without forgetting the animation
+    Pause(Pause),
+    /// Continue running frames once nothing else is pausing the executor
+    Resume(Pause),
     /// Shutdown the animation executor
     Shutdown,
 }
 
 pub type SharedAnimator = Arc<Animator>;
 
-/// Handle running animations on the light strip
+/// The reasons animations can be paused. Animations only run again once every reason they were
+/// paused for has been resumed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pause {
+    /// A notification is being shown over the animations
+    Notification,
+    /// The strip is turned off
+    PowerOff,
+}
+
+/// The id of the animation running on each segment, the entire strip is keyed by `None`
+pub type RunningAnimations = BTreeMap<Option<String>, String>;
+
+/// Where each segment is in the playlist it is playing, the entire strip is keyed by `None`
+pub type Positions = BTreeMap<Option<String>, Position>;
+
+/// The metadata of each registered animation by id
+type SharedMetadata = Arc<Mutex<BTreeMap<String, Metadata>>>;
+
+/// Reports which animation an executor is running
+#[derive(Clone, Debug)]
+struct Tracker {
+    segment: Option<String>,
+    running: Arc<watch::Sender<RunningAnimations>>,
+    positions: Arc<watch::Sender<Positions>>,
+}
+
+impl Tracker {
+    /// Record the animation that is now running, if any
+    fn set(&self, id: Option<&str>) {
+        self.running.send_modify(|running| match id {
+            Some(id) => {
+                running.insert(self.segment.clone(), id.to_owned());
+            }
+            None => {
+                running.remove(&self.segment);
+            }
+        });
+    }
+
+    /// The id of the animation that is running, if any
+    fn current(&self) -> Option<String> {
+        self.running.borrow().get(&self.segment).cloned()
+    }
+
+    /// Record where the executor is in its playlist, if it is playing one
+    fn set_position(&self, position: Option<Position>) {
+        self.positions.send_modify(|positions| match position {
+            Some(position) => {
+                positions.insert(self.segment.clone(), position);
+            }
+            None => {
+                positions.remove(&self.segment);
+            }
+        });
+    }
+}
+
+/// Handle running animations on the light strip. The entire strip and each of its segments get
+/// their own executor so that they can all run different animations at the same time. Animations
+/// draw to their own layer so they never overwrite the colors set over the API. Playlists and the
+/// metadata of each animation are saved next to the animations directory.
 #[derive(Clone, Debug)]
 pub struct Animator {
     base_path: PathBuf,
     development: bool,
+    geometry: Geometry,
     pixels: Pixels,
-    tx: Sender<Action>,
+    strip: Sender<Action>,
+    segments: HashMap<String, Sender<Action>>,
+    running: watch::Receiver<RunningAnimations>,
+    positions: watch::Receiver<Positions>,
+    playlists: Arc<Mutex<BTreeMap<String, Playlist>>>,
+    metadata: SharedMetadata,
 }
 
 impl Animator {
     /// Create and start a new animator
-    pub fn new<P: Into<PathBuf>>(
-        base_path: P,
-        development: bool,
-        pixels: Pixels,
-    ) -> (SharedAnimator, JoinHandle<()>) {
-        let base_path = base_path.into();
+    pub fn new(config: &Config, pixels: Pixels) -> (SharedAnimator, JoinHandle<()>) {
+        let base_path = config.animations_path.clone();
+        let geometry = Geometry::new(config);
+        let pixels = pixels.layer(LayerId::Animation);
+        let (running_tx, running) = watch::channel(RunningAnimations::new());
+        let running_tx = Arc::new(running_tx);
+        let (positions_tx, positions) = watch::channel(Positions::new());
+        let positions_tx = Arc::new(positions_tx);
+        let metadata = SharedMetadata::default();
+        let mut handles = Vec::new();
 
-        // Create the control channel
-        let (tx, rx) = mpsc::channel(5);
+        // Launch the executor for the entire strip
+        let (strip, rx) = mpsc::channel(5);
+        let tracker = Tracker {
+            segment: None,
+            running: running_tx.clone(),
+            positions: positions_tx.clone(),
+        };
+        let span = info_span!("animator");
+        handles.push(task::spawn(
+            executor(
+                base_path.clone(),
+                pixels.clone(),
+                geometry,
+                metadata.clone(),
+                tracker,
+                rx,
+            )
+            .instrument(span),
+        ));
+
+        // Launch an executor for each segment
+        let mut segments = HashMap::new();
+        for name in pixels.segments() {
+            let (tx, rx) = mpsc::channel(5);
+            let segment_pixels = pixels.segment(name).unwrap();
+            let tracker = Tracker {
+                segment: Some(name.to_owned()),
+                running: running_tx.clone(),
+                positions: positions_tx.clone(),
+            };
+            let span = info_span!("animator", segment = %name);
+            handles.push(task::spawn(
+                executor(
+                    base_path.clone(),
+                    segment_pixels,
+                    geometry,
+                    metadata.clone(),
+                    tracker,
+                    rx,
+                )
+                .instrument(span),
+            ));
+            segments.insert(name.to_owned(), tx);
+        }
 
-        // Launch the executor
-        let executor_path = base_path.clone();
-        let executor_pixels = pixels.clone();
-        let handle = task::spawn(executor(executor_path, executor_pixels, rx));
+        // Wait for all the executors to exit
+        let handle = task::spawn(async move {
+            for handle in handles {
+                if let Err(err) = handle.await {
+                    error!(%err, "animation executor failed");
+                }
+            }
+        });
 
         (
             Arc::new(Self {
                 base_path,
-                development,
+                development: config.development,
+                geometry,
                 pixels,
-                tx,
+                strip,
+                segments,
+                running,
+                positions,
+                playlists: Arc::default(),
+                metadata,
             }),
             handle,
         )
     }
 
-    /// Compile and save an animation to disk
+    /// Get the executor for a segment, or the entire strip if no segment is given
+    fn executor(&self, segment: Option<&str>) -> Result<&Sender<Action>, UnknownSegment> {
+        match segment {
+            Some(name) => self
+                .segments
+                .get(name)
+                .ok_or_else(|| UnknownSegment(name.to_owned())),
+            None => Ok(&self.strip),
+        }
+    }
+
+    /// Watch which animation is running on each segment
+    pub fn running(&self) -> watch::Receiver<RunningAnimations> {
+        self.running.clone()
+    }
+
+    /// Watch where each segment is in the playlist it is playing
+    pub fn positions(&self) -> watch::Receiver<Positions> {
+        self.positions.clone()
+    }
+
+    /// Where the saved playlists are stored
+    fn playlists_path(&self) -> PathBuf {
+        self.base_path.with_file_name("playlists.toml")
+    }
+
+    /// Load the saved playlists from disk
+    #[instrument(skip(self))]
+    pub async fn load_playlists(&self) -> Result<(), StateError> {
+        let loaded = state::load::<BTreeMap<String, Playlist>>(&self.playlists_path())
+            .await?
+            .unwrap_or_default();
+        info!(count = %loaded.len(), "loaded playlists");
+
+        *self.playlists.lock().await = loaded;
+        Ok(())
+    }
+
+    /// Where the metadata of the registered animations is stored
+    fn metadata_path(&self) -> PathBuf {
+        self.base_path.with_file_name("animations.toml")
+    }
+
+    /// Load the metadata of the registered animations from disk
+    #[instrument(skip(self))]
+    pub async fn load_metadata(&self) -> Result<(), StateError> {
+        let loaded = state::load::<BTreeMap<String, Metadata>>(&self.metadata_path())
+            .await?
+            .unwrap_or_default();
+        info!(count = %loaded.len(), "loaded animation metadata");
+
+        *self.metadata.lock().await = loaded;
+        Ok(())
+    }
+
+    /// Save a playlist, replacing any existing playlist with the same name. Executors which are
+    /// already playing the playlist keep playing the previous version.
+    #[instrument(skip(self, playlist))]
+    pub async fn save_playlist(&self, name: &str, playlist: Playlist) -> Result<(), StateError> {
+        let mut playlists = self.playlists.lock().await;
+        playlists.insert(name.to_owned(), playlist);
+        state::save(&*playlists, &self.playlists_path()).await
+    }
+
+    /// Delete a playlist by name. This method is idempotent.
+    #[instrument(skip(self))]
+    pub async fn delete_playlist(&self, name: &str) -> Result<(), StateError> {
+        let mut playlists = self.playlists.lock().await;
+        if playlists.remove(name).is_some() {
+            state::save(&*playlists, &self.playlists_path()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get all the saved playlists by name
+    pub async fn playlists(&self) -> BTreeMap<String, Playlist> {
+        self.playlists.lock().await.clone()
+    }
+
+    /// Play a saved playlist on a segment, or the entire strip if no segment is given
+    #[instrument(skip(self))]
+    pub async fn play(&self, segment: Option<&str>, name: &str) -> Result<(), PlayError> {
+        let executor = self.executor(segment)?;
+        let playlist = self
+            .playlists
+            .lock()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| PlayError::UnknownPlaylist(name.to_owned()))?;
+
+        if let Err(err) = executor.send(Action::Play(name.to_owned(), playlist)).await {
+            error!(%err, "failed to play playlist");
+        }
+
+        Ok(())
+    }
+
+    /// Compile and save an animation to disk along with its metadata, which is returned
     #[instrument(skip(self, wasm))]
     pub async fn register<B: AsRef<[u8]>>(
         &self,
         id: &str,
         wasm: B,
-    ) -> Result<(), RegistrationError> {
-        let animation = Animation::build(wasm, self.development, self.pixels.clone())?;
+    ) -> Result<Metadata, RegistrationError> {
+        let wasm = wasm.as_ref();
+        let animation =
+            Animation::build(wasm, self.development, self.pixels.clone(), self.geometry)?;
+        let metadata = Metadata::from_wasm(wasm).map_err(BuildError::from)?;
         animation.save(id, &self.base_path).await?;
 
+        let mut all = self.metadata.lock().await;
+        all.insert(id.to_owned(), metadata.clone());
+        state::save(&*all, &self.metadata_path()).await?;
+
+        Ok(metadata)
+    }
+
+    /// Delete an animation and its metadata from disk
+    #[instrument(skip(self))]
+    pub async fn remove(&self, id: &str) -> Result<(), StateError> {
+        Animation::remove(id, &self.base_path).await?;
+
+        let mut all = self.metadata.lock().await;
+        if all.remove(id).is_some() {
+            state::save(&*all, &self.metadata_path()).await?;
+        }
+
         Ok(())
     }
 
-    /// Delete an animation from disk
+    /// Get the metadata of a registered animation. Animations registered before they could declare
+    /// metadata have none.
     #[instrument(skip(self))]
-    pub async fn remove(&self, id: &str) -> Result<(), io::Error> {
-        Animation::remove(id, &self.base_path).await
+    pub async fn animation(&self, id: &str) -> Result<Option<Metadata>, io::Error> {
+        match fs::metadata(self.base_path.join(id)).await {
+            Ok(info) if info.is_file() => {}
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let metadata = self.metadata.lock().await.get(id).cloned();
+        Ok(Some(metadata.unwrap_or_default()))
     }
 
-    /// Start an animation
+    /// Get the metadata of every registered animation by id
     #[instrument(skip(self))]
-    pub async fn start(&self, id: &str) {
-        if let Err(err) = self.tx.send(Action::Start(id.into())).await {
+    pub async fn animations(&self) -> Result<BTreeMap<String, Metadata>, io::Error> {
+        let all = self.metadata.lock().await.clone();
+        let mut animations = BTreeMap::new();
+
+        let mut entries = fs::read_dir(&self.base_path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            if let Some(id) = entry.file_name().to_str() {
+                let metadata = all.get(id).cloned().unwrap_or_default();
+                animations.insert(id.to_owned(), metadata);
+            }
+        }
+
+        Ok(animations)
+    }
+
+    /// Start an animation on a segment, or the entire strip if no segment is given. The animation
+    /// uses the defaults for all of its parameters.
+    #[instrument(skip(self))]
+    pub async fn start(&self, segment: Option<&str>, id: &str) -> Result<(), UnknownSegment> {
+        let executor = self.executor(segment)?;
+        if let Err(err) = executor.send(Action::Start(id.into(), Values::new())).await {
+            error!(%err, "failed to start animation");
+        }
+
+        Ok(())
+    }
+
+    /// Start an animation on a segment, or the entire strip if no segment is given, customized by
+    /// some of its parameters. Any parameters which aren't given use their defaults.
+    #[instrument(skip(self))]
+    pub async fn start_with(
+        &self,
+        segment: Option<&str>,
+        id: &str,
+        values: Values,
+    ) -> Result<(), ParameterError> {
+        let executor = self.executor(segment)?;
+        self.check(id, &values).await?;
+
+        if let Err(err) = executor.send(Action::Start(id.into(), values)).await {
             error!(%err, "failed to start animation");
         }
+
+        Ok(())
     }
 
-    /// Stop the currently running animation
+    /// Change some of the parameters of the animation running on a segment, or the entire strip
+    /// if no segment is given, without restarting it
     #[instrument(skip(self))]
-    pub async fn stop(&self) {
-        if let Err(err) = self.tx.send(Action::Stop).await {
+    pub async fn configure(
+        &self,
+        segment: Option<&str>,
+        values: Values,
+    ) -> Result<(), ParameterError> {
+        let executor = self.executor(segment)?;
+        let id = self
+            .running
+            .borrow()
+            .get(&segment.map(str::to_owned))
+            .cloned()
+            .ok_or(ParameterError::NotRunning)?;
+        self.check(&id, &values).await?;
+
+        if let Err(err) = executor.send(Action::Configure(id, values)).await {
+            error!(%err, "failed to configure animation");
+        }
+
+        Ok(())
+    }
+
+    /// Ensure parameters fit the schema declared by an animation
+    async fn check(&self, id: &str, values: &Values) -> Result<(), ParameterError> {
+        match self.metadata.lock().await.get(id) {
+            Some(metadata) => metadata.check(values),
+            None => Metadata::default().check(values),
+        }
+    }
+
+    /// Stop the animation or playlist running on a segment, or the entire strip if no segment is
+    /// given
+    #[instrument(skip(self))]
+    pub async fn stop(&self, segment: Option<&str>) -> Result<(), UnknownSegment> {
+        let executor = self.executor(segment)?;
+        if let Err(err) = executor.send(Action::Stop(None)).await {
             error!(%err, "failed to stop animation");
         }
+
+        Ok(())
     }
 
-    /// Shutdown the executor
+    /// Stop the animations running on the entire strip and every segment, waiting until each of
+    /// them has been cleared
     #[instrument(skip(self))]
-    pub async fn shutdown(&self) {
-        if let Err(err) = self.tx.send(Action::Shutdown).await {
-            error!(%err, "failed to shutdown executor");
+    pub async fn stop_all(&self) {
+        let (tx, mut rx) = mpsc::channel(1);
+        self.broadcast(Action::Stop(Some(tx)), "failed to stop animation")
+            .await;
+
+        // Every executor drops its copy of the sender once it has stopped
+        while rx.recv().await.is_some() {}
+    }
+
+    /// Freeze the animations on every executor where they are. Animations which are started while
+    /// paused only begin running once resumed.
+    #[instrument(skip(self))]
+    pub async fn pause(&self, reason: Pause) {
+        self.broadcast(Action::Pause(reason), "failed to pause executor")
+            .await
+    }
+
+    /// Continue running the animations on every executor, unless they are still paused for
+    /// another reason
+    #[instrument(skip(self))]
+    pub async fn resume(&self, reason: Pause) {
+        self.broadcast(Action::Resume(reason), "failed to resume executor")
+            .await
+    }
+
+    /// Send the same action to every executor
+    async fn broadcast(&self, action: Action, message: &str) {
+        for executor in self.segments.values().chain([&self.strip]) {
+            if let Err(err) = executor.send(action.clone()).await {
+                error!(%err, "{}", message);
+            }
         }
     }
+
+    /// Shutdown all the executors
+    #[instrument(skip(self))]
+    pub async fn shutdown(&self) {
+        self.broadcast(Action::Shutdown, "failed to shutdown executor")
+            .await
+    }
 }
 
-/// Waits for an animation to be received and then runs it
-#[instrument(name = "animator", skip_all)]
-async fn executor(path: PathBuf, pixels: Pixels, mut actions: Receiver<Action>) {
+/// Waits for an animation to be received and then runs it. While playing a playlist, the
+/// animation is swapped out whenever the current entry finishes.
+async fn executor(
+    path: PathBuf,
+    pixels: Pixels,
+    geometry: Geometry,
+    metadata: SharedMetadata,
+    tracker: Tracker,
+    mut actions: Receiver<Action>,
+) {
     info!("animator started");
     let mut animation: Option<Animation> = None;
+    let mut playlist: Option<Queue> = None;
+    let mut paused = HashSet::new();
 
     loop {
-        match &animation {
-            None => match actions.recv().await {
-                Some(Action::Start(id)) => {
-                    match Animation::load(&id, &path, pixels.clone()).await {
-                        Ok(a) => animation = Some(a),
-                        Err(err) => error!(%err, "failed to load animation"),
+        // Move on to the next entry once the current one finishes
+        if let Some(queue) = playlist.as_mut().filter(|_| paused.is_empty()) {
+            if queue.deadline() <= time::Instant::now() {
+                pixels.transition(queue.transition());
+                animation = match queue.advance() {
+                    Some(entry) => {
+                        let values = Values::new();
+                        load(
+                            &entry.id, values, &path, &pixels, geometry, &metadata, &tracker,
+                        )
+                        .await
+                    }
+                    None => {
+                        playlist = None;
+                        None
                     }
+                };
+                if animation.is_none() {
+                    tracker.set(None);
+                    clear(&pixels);
                 }
-                Some(Action::Stop) => continue, // Already stopped, nothing to do
-                Some(Action::Shutdown) | None => break, // Exit when the channel closes
-            },
-            Some(a) => {
-                // Execute a frame
+                tracker.set_position(playlist.as_ref().and_then(Queue::position));
+            }
+        }
+
+        let action = match &animation {
+            Some(a) if paused.is_empty() => {
+                // Execute a frame. Animations block while they run, so let the runtime move other
+                // tasks off of this thread in the meantime.
                 let method = a.animate().unwrap();
-                if let Err(err) = method.call() {
+                if let Err(err) = task::block_in_place(|| method.call()) {
                     animation = None;
+                    tracker.set(None);
+                    clear(&pixels);
                     error!(%err, "an error occurred while executing the animation");
                 }
 
                 // Check if there is an action waiting
                 match actions.try_recv() {
-                    Ok(Action::Start(id)) => {
-                        match Animation::load(&id, &path, pixels.clone()).await {
-                            Ok(a) => animation = Some(a),
-                            Err(err) => error!(%err, "failed to load animation"),
-                        }
-                    }
-                    Ok(Action::Stop) => animation = None, // Stop the animation
+                    Ok(action) => action,
                     Err(TryRecvError::Empty) => continue, // No action, just continue to the next frame
-                    Ok(Action::Shutdown) | Err(TryRecvError::Disconnected) => break, // Exit when channel closes
+                    Err(TryRecvError::Disconnected) => break, // Exit when channel closes
+                }
+            }
+            // Nothing to run, wait until there is something to do or the playlist moves on
+            _ => {
+                let deadline = playlist
+                    .as_ref()
+                    .filter(|_| paused.is_empty())
+                    .map(Queue::deadline);
+
+                tokio::select! {
+                    action = actions.recv() => match action {
+                        Some(action) => action,
+                        None => break, // Exit when the channel closes
+                    },
+                    _ = time::sleep_until(deadline.unwrap_or_else(time::Instant::now)), if deadline.is_some() => continue,
+                }
+            }
+        };
+
+        match action {
+            Action::Start(id, values) => {
+                if playlist.take().is_some() {
+                    tracker.set_position(None);
+                }
+
+                // Keep running the previous animation if the new one can't be loaded
+                if let Some(a) =
+                    load(&id, values, &path, &pixels, geometry, &metadata, &tracker).await
+                {
+                    animation = Some(a);
                 }
             }
+            Action::Configure(id, values) => {
+                // Ignore changes meant for an animation which has since been replaced
+                if let Some(a) = animation.as_ref().filter(|_| tracker.current() == Some(id)) {
+                    a.parameters().update(values);
+                }
+            }
+            Action::Play(name, list) => {
+                let mut queue = Queue::new(name, list);
+                pixels.transition(queue.transition());
+                animation = match queue.start() {
+                    Some(entry) => {
+                        let values = Values::new();
+                        load(
+                            &entry.id, values, &path, &pixels, geometry, &metadata, &tracker,
+                        )
+                        .await
+                    }
+                    None => None,
+                };
+                if animation.is_none() {
+                    tracker.set(None);
+                    clear(&pixels);
+                }
+                tracker.set_position(queue.position());
+                playlist = Some(queue);
+            }
+            Action::Stop(done) => {
+                if playlist.take().is_some() {
+                    tracker.set_position(None);
+                }
+
+                // Stop the animation and reveal the layers below
+                if animation.take().is_some() {
+                    tracker.set(None);
+                    clear(&pixels);
+                }
+                drop(done);
+            }
+            Action::Pause(reason) => {
+                paused.insert(reason);
+            }
+            Action::Resume(reason) => {
+                paused.remove(&reason);
+            }
+            Action::Shutdown => break,
         }
     }
 
     info!("shutdown successfully")
 }
+
+/// Clear whatever was drawn and load an animation to replace it, filling in the defaults for any
+/// parameters which aren't given
+async fn load(
+    id: &str,
+    values: Values,
+    path: &Path,
+    pixels: &Pixels,
+    geometry: Geometry,
+    metadata: &SharedMetadata,
+    tracker: &Tracker,
+) -> Option<Animation> {
+    let parameters = match metadata.lock().await.get(id) {
+        Some(metadata) => Parameters::new(metadata, values),
+        None => Parameters::new(&Metadata::default(), values),
+    };
+
+    match Animation::load(id, path, pixels.clone(), parameters, geometry).await {
+        Ok(animation) => {
+            clear(pixels);
+            tracker.set(Some(id));
+            Some(animation)
+        }
+        Err(err) => {
+            error!(%err, "failed to load animation");
+            None
+        }
+    }
+}
+
+/// Remove everything the previous animation drew
+fn clear(pixels: &Pixels) {
+    pixels.clear();
+    pixels.show();
+}
@@ -0,0 +1,221 @@
+use super::{Metadata, ParameterColor, ParameterError, ParameterKind};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+/// The value of a parameter an animation is customized with
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Color(ParameterColor),
+    Boolean(bool),
+    String(String),
+}
+
+/// Values for the parameters of an animation, by name
+pub type Values = BTreeMap<String, Value>;
+
+/// The parameters an animation is running with. These are shared with the running instance so
+/// they can be changed without restarting it.
+#[derive(Clone, Debug, Default)]
+pub struct Parameters(Arc<RwLock<Values>>);
+
+impl Parameters {
+    /// Fill in the defaults declared by an animation for any parameters that aren't given
+    pub fn new(metadata: &Metadata, mut values: Values) -> Self {
+        for (name, parameter) in &metadata.parameters {
+            values
+                .entry(name.to_owned())
+                .or_insert_with(|| parameter.kind.default_value());
+        }
+
+        Parameters(Arc::new(RwLock::new(values)))
+    }
+
+    /// Get the current value of a parameter
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.0.read().unwrap().get(name).cloned()
+    }
+
+    /// Replace the values of some parameters, leaving the rest as they are
+    pub fn update(&self, values: Values) {
+        self.0.write().unwrap().extend(values);
+    }
+}
+
+impl ParameterKind {
+    /// The value used when none is given
+    pub fn default_value(&self) -> Value {
+        match self {
+            ParameterKind::Number { default, .. } => Value::Number(*default),
+            ParameterKind::Color { default } => Value::Color(*default),
+            ParameterKind::Boolean { default } => Value::Boolean(*default),
+            ParameterKind::String { default, .. } => Value::String(default.clone()),
+        }
+    }
+}
+
+impl Metadata {
+    /// Ensure each value is for a parameter the animation declares and fits its schema
+    pub fn check(&self, values: &Values) -> Result<(), ParameterError> {
+        for (name, value) in values {
+            let parameter = self
+                .parameters
+                .get(name)
+                .ok_or_else(|| ParameterError::Unknown(name.to_owned()))?;
+            let invalid = |reason| Err(ParameterError::Invalid(name.to_owned(), reason));
+
+            match (&parameter.kind, value) {
+                (ParameterKind::Number { min, max, .. }, Value::Number(n)) => {
+                    if !n.is_finite() {
+                        return invalid("must be finite");
+                    }
+                    if matches!(min, Some(min) if n < min) || matches!(max, Some(max) if n > max) {
+                        return invalid("out of range");
+                    }
+                }
+                (ParameterKind::String { options, .. }, Value::String(s)) => {
+                    if !options.is_empty() && !options.contains(s) {
+                        return invalid("must be one of the options");
+                    }
+                }
+                (ParameterKind::Color { .. }, Value::Color(_))
+                | (ParameterKind::Boolean { .. }, Value::Boolean(_)) => {}
+                (ParameterKind::Number { .. }, _) => return invalid("must be a number"),
+                (ParameterKind::Color { .. }, _) => return invalid("must be a color"),
+                (ParameterKind::Boolean { .. }, _) => return invalid("must be a boolean"),
+                (ParameterKind::String { .. }, _) => return invalid("must be a string"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> Metadata {
+        toml::from_str(
+            r#"
+            [parameters.speed]
+            type = "number"
+            default = 1.0
+            min = 1.0
+            max = 32.0
+
+            [parameters.tint]
+            type = "color"
+            default = { r = 255, g = 128, b = 0 }
+
+            [parameters.reverse]
+            type = "boolean"
+            default = false
+
+            [parameters.mode]
+            type = "string"
+            default = "smooth"
+            options = ["smooth", "steps"]
+
+            [parameters.label]
+            type = "string"
+            default = ""
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn values(values: &[(&str, Value)]) -> Values {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    /// The reason a single value is rejected
+    fn invalid(name: &str, value: Value) -> ParameterError {
+        metadata().check(&values(&[(name, value)])).unwrap_err()
+    }
+
+    #[test]
+    fn fills_defaults() {
+        let parameters = Parameters::new(&metadata(), values(&[("speed", Value::Number(4.0))]));
+        assert_eq!(parameters.get("speed"), Some(Value::Number(4.0)));
+        assert_eq!(parameters.get("reverse"), Some(Value::Boolean(false)));
+        assert_eq!(
+            parameters.get("mode"),
+            Some(Value::String("smooth".to_string()))
+        );
+        assert_eq!(
+            parameters.get("tint"),
+            Some(Value::Color(ParameterColor {
+                r: 255,
+                g: 128,
+                b: 0,
+                w: None
+            }))
+        );
+        assert_eq!(parameters.get("missing"), None);
+    }
+
+    #[test]
+    fn updates_some() {
+        let parameters = Parameters::new(&metadata(), Values::new());
+        parameters.update(values(&[("reverse", Value::Boolean(true))]));
+        assert_eq!(parameters.get("reverse"), Some(Value::Boolean(true)));
+        assert_eq!(parameters.get("speed"), Some(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn accepts_valid() {
+        let valid = values(&[
+            ("speed", Value::Number(32.0)),
+            ("tint", Value::Color(ParameterColor::default())),
+            ("reverse", Value::Boolean(true)),
+            ("mode", Value::String("steps".to_string())),
+            ("label", Value::String("anything".to_string())),
+        ]);
+        assert!(metadata().check(&valid).is_ok());
+        assert!(metadata().check(&Values::new()).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown() {
+        assert!(matches!(
+            invalid("missing", Value::Boolean(true)),
+            ParameterError::Unknown(name) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let reason = |name, value| match invalid(name, value) {
+            ParameterError::Invalid(invalid, reason) if invalid == name => reason,
+            err => panic!("unexpected error {:?}", err),
+        };
+
+        assert_eq!(reason("speed", Value::Number(f64::NAN)), "must be finite");
+        assert_eq!(
+            reason("speed", Value::Number(f64::INFINITY)),
+            "must be finite"
+        );
+        assert_eq!(reason("speed", Value::Number(0.5)), "out of range");
+        assert_eq!(reason("speed", Value::Number(32.5)), "out of range");
+        assert_eq!(
+            reason("mode", Value::String("jumps".to_string())),
+            "must be one of the options"
+        );
+        assert_eq!(reason("speed", Value::Boolean(true)), "must be a number");
+        assert_eq!(reason("tint", Value::Number(1.0)), "must be a color");
+        assert_eq!(
+            reason("reverse", Value::String("true".to_string())),
+            "must be a boolean"
+        );
+        assert_eq!(
+            reason("label", Value::Color(ParameterColor::default())),
+            "must be a string"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, SystemTime},
+};
+use tokio::time::Instant;
+
+/// A list of animations which are played one after another
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Playlist {
+    /// Play the entries in a random order, which is shuffled again each time through
+    #[serde(default)]
+    pub shuffle: bool,
+    /// Start over once the last entry finishes, otherwise the animation stops
+    #[serde(default)]
+    pub repeat: bool,
+    /// How long to crossfade between entries in milliseconds
+    #[serde(default)]
+    pub transition: u64,
+    pub entries: Vec<Entry>,
+}
+
+/// An animation in a playlist
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Entry {
+    /// The id of the animation to run
+    pub id: String,
+    /// How long the animation runs for in seconds
+    pub duration: u64,
+}
+
+/// Where an executor is in the playlist it is playing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// The name of the playlist
+    pub playlist: String,
+    /// The index of the current entry in the playlist
+    pub index: usize,
+    /// The id of the animation that is running
+    pub id: String,
+    /// When the playlist moves on to the next entry
+    pub ends: SystemTime,
+}
+
+/// Tracks the progress through a playlist as it is played
+#[derive(Debug)]
+pub(crate) struct Queue {
+    name: String,
+    playlist: Playlist,
+    order: Vec<usize>,
+    position: usize,
+    deadline: Instant,
+}
+
+impl Queue {
+    /// Prepare to play a playlist from the start
+    pub fn new(name: String, playlist: Playlist) -> Self {
+        let mut queue = Queue {
+            name,
+            order: (0..playlist.entries.len()).collect(),
+            playlist,
+            position: 0,
+            deadline: Instant::now(),
+        };
+        queue.shuffle();
+        queue
+    }
+
+    /// Randomize the order of the entries when shuffling
+    fn shuffle(&mut self) {
+        if !self.playlist.shuffle {
+            return;
+        }
+
+        let mut random = Random::new();
+        for i in (1..self.order.len()).rev() {
+            let j = (random.next() % (i as u64 + 1)) as usize;
+            self.order.swap(i, j);
+        }
+    }
+
+    /// When the current entry finishes
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// How long to crossfade between entries
+    pub fn transition(&self) -> Duration {
+        Duration::from_millis(self.playlist.transition)
+    }
+
+    /// Start the first entry of the playlist
+    pub fn start(&mut self) -> Option<&Entry> {
+        self.position = 0;
+        self.begin()
+    }
+
+    /// Move on to the next entry, if there are any left
+    pub fn advance(&mut self) -> Option<&Entry> {
+        self.position += 1;
+        if self.position >= self.order.len() {
+            if !self.playlist.repeat {
+                return None;
+            }
+
+            self.position = 0;
+            self.shuffle();
+        }
+
+        self.begin()
+    }
+
+    /// Start the timer for the current entry
+    fn begin(&mut self) -> Option<&Entry> {
+        let entry = self.playlist.entries.get(*self.order.get(self.position)?)?;
+        self.deadline = Instant::now() + Duration::from_secs(entry.duration);
+        Some(entry)
+    }
+
+    /// Report where the queue is in the playlist
+    pub fn position(&self) -> Option<Position> {
+        let index = *self.order.get(self.position)?;
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        Some(Position {
+            playlist: self.name.clone(),
+            index,
+            id: self.playlist.entries[index].id.clone(),
+            ends: SystemTime::now() + remaining,
+        })
+    }
+}
+
+/// A small xorshift generator, which is plenty for shuffling a playlist
+struct Random(u64);
+
+impl Random {
+    /// Seed the generator from the randomness the standard library uses for hash maps
+    fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Random(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(entries: usize, shuffle: bool, repeat: bool) -> Queue {
+        let playlist = Playlist {
+            shuffle,
+            repeat,
+            transition: 0,
+            entries: (0..entries)
+                .map(|i| Entry {
+                    id: i.to_string(),
+                    duration: 1,
+                })
+                .collect(),
+        };
+        Queue::new("test".to_string(), playlist)
+    }
+
+    /// The ids of the entries played in one pass through the queue, from its current position
+    fn pass(queue: &mut Queue, first: Option<&Entry>) -> Vec<String> {
+        let mut ids = vec![first.unwrap().id.clone()];
+        for _ in 1..queue.order.len() {
+            ids.push(queue.advance().unwrap().id.clone());
+        }
+        ids
+    }
+
+    #[test]
+    fn stops_at_end() {
+        let mut queue = queue(3, false, false);
+        assert_eq!(queue.start().unwrap().id, "0");
+        assert_eq!(queue.advance().unwrap().id, "1");
+        assert_eq!(queue.advance().unwrap().id, "2");
+        assert_eq!(queue.advance(), None);
+        assert_eq!(queue.position(), None);
+    }
+
+    #[test]
+    fn repeats() {
+        let mut queue = queue(3, false, true);
+        let first = queue.start().cloned();
+        assert_eq!(pass(&mut queue, first.as_ref()), ["0", "1", "2"]);
+        assert_eq!(queue.advance().unwrap().id, "0");
+        assert_eq!(queue.position().unwrap().index, 0);
+    }
+
+    #[test]
+    fn empty() {
+        let mut queue = queue(0, true, true);
+        assert_eq!(queue.start(), None);
+        assert_eq!(queue.advance(), None);
+    }
+
+    #[test]
+    fn shuffles_every_pass() {
+        let mut queue = queue(8, true, true);
+        let first = queue.start().cloned();
+        let mut passes = vec![pass(&mut queue, first.as_ref())];
+        for _ in 0..4 {
+            let first = queue.advance().cloned();
+            passes.push(pass(&mut queue, first.as_ref()));
+        }
+
+        // Every pass plays each entry once
+        let all: Vec<_> = (0..8).map(|i| i.to_string()).collect();
+        for ids in &passes {
+            let mut sorted = ids.clone();
+            sorted.sort();
+            assert_eq!(sorted, all);
+        }
+
+        // Five passes in the same order has a chance of 1 in 8!^4
+        assert!(passes.iter().any(|ids| ids != &passes[0]));
+    }
+
+    #[test]
+    fn shuffle_is_permutation() {
+        let mut queue = queue(10, true, false);
+        for _ in 0..100 {
+            queue.shuffle();
+            let mut order = queue.order.clone();
+            order.sort_unstable();
+            assert_eq!(order, (0..10).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{config::Config, errors::ClipError, pixels::Clip};
+use std::{collections::BTreeMap, io::ErrorKind, path::PathBuf};
+use tokio::fs;
+use tracing::{debug, instrument, warn};
+
+/// The extension of saved clips
+const EXTENSION: &str = "clip";
+
+/// Saves recorded clips as files in a directory next to the animations
+#[derive(Clone, Debug)]
+pub struct Clips {
+    path: PathBuf,
+}
+
+impl Clips {
+    pub fn new(config: &Config) -> Clips {
+        Clips {
+            path: config.clips_path(),
+        }
+    }
+
+    /// Get the path of a clip. Names are limited to letters, numbers, dashes, and underscores so
+    /// that they can't refer to files outside the directory.
+    fn path(&self, name: &str) -> Result<PathBuf, ClipError> {
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if name.is_empty() || !valid {
+            return Err(ClipError::InvalidName(name.to_owned()));
+        }
+
+        Ok(self.path.join(name).with_extension(EXTENSION))
+    }
+
+    /// Get the contents of a saved clip in its binary format
+    #[instrument(skip(self))]
+    pub async fn read(&self, name: &str) -> Result<Vec<u8>, ClipError> {
+        match fs::read(self.path(name)?).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(ClipError::UnknownClip(name.to_owned()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load a saved clip
+    pub async fn load(&self, name: &str) -> Result<Clip, ClipError> {
+        Clip::decode(&self.read(name).await?)
+    }
+
+    /// Save a clip, replacing any existing clip with the same name
+    #[instrument(skip(self, clip))]
+    pub async fn save(&self, name: &str, clip: &Clip) -> Result<(), ClipError> {
+        let path = self.path(name)?;
+        fs::create_dir_all(&self.path).await?;
+
+        // Write to a temporary file first so a partially written clip is never left behind
+        let temporary = path.with_extension("clip.tmp");
+        fs::write(&temporary, clip.encode()).await?;
+        fs::rename(&temporary, &path).await?;
+
+        debug!("saved clip");
+        Ok(())
+    }
+
+    /// Delete a saved clip. Deleting a clip that doesn't exist is not an error.
+    #[instrument(skip(self))]
+    pub async fn delete(&self, name: &str) -> Result<(), ClipError> {
+        match fs::remove_file(self.path(name)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get all the saved clips by name, skipping any that can't be read
+    #[instrument(skip(self))]
+    pub async fn list(&self) -> Result<BTreeMap<String, Clip>, ClipError> {
+        let mut clips = BTreeMap::new();
+
+        let mut entries = match fs::read_dir(&self.path).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(clips),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+
+            match self.load(&name).await {
+                Ok(clip) => {
+                    clips.insert(name, clip);
+                }
+                Err(err) => warn!(%name, %err, "skipping unreadable clip"),
+            }
+        }
+
+        Ok(clips)
+    }
+}
@@ -1,12 +1,13 @@
+use crate::schedule::{Location, TimeZone};
 use eyre::{eyre, WrapErr};
 use serde::{de::Error, Deserialize, Deserializer};
-use std::{env, net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{collections::BTreeMap, env, net::SocketAddr, path::PathBuf, str::FromStr};
 use tokio::fs;
 use tracing::Level;
 
 static DEFAULT_CONFIG_PATH: &'static str = "/etc/lights/config.toml";
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Config {
     /// The host and port to listen on
     pub address: SocketAddr,
@@ -17,22 +18,681 @@ pub struct Config {
     /// The total amount of LEDs on the strip
     pub leds: u16,
 
+    /// The number of LEDs per meter
+    pub strip_density: u16,
+
+    /// The length of the strip in meters
+    pub strip_length: u16,
+
+    /// The kind of LEDs on the strip
+    pub strip_type: StripType,
+
+    /// The order the LEDs expect to receive their color channels in
+    pub color_order: ColorOrder,
+
+    /// Where frames are written
+    pub output: Output,
+
+    /// Remote devices that frames are also sent to over the network
+    pub network: Vec<Destination>,
+
+    /// How colors are corrected before being sent to the strip
+    pub calibration: Calibration,
+
+    /// How much current the strip is allowed to draw
+    pub power: Power,
+
+    /// How frames are written to the strip
+    pub render: Render,
+
+    /// What the strip shows when the controller starts
+    pub startup: Startup,
+
+    /// How schedules are run
+    pub scheduler: Scheduler,
+
+    /// How clips are recorded
+    pub clips: Clips,
+
+    /// How the strip is shown by the mock output
+    pub mock: Mock,
+
     /// The minimum level to log at
     pub log_level: Level,
 
     /// Whether to run in development mode
     pub development: bool,
+
+    /// Named sections of the strip that can be controlled independently, ordered by their start
+    pub segments: Vec<Segment>,
+}
+
+/// The kinds of LED strips that can be driven
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StripType {
+    /// RGB strips using WS2811 drivers
+    Ws2811,
+    /// RGB strips using WS2812 LEDs
+    #[default]
+    Ws2812,
+    /// RGB strips using SK6812 LEDs
+    Sk6812,
+    /// RGBW strips using SK6812 LEDs with a dedicated white channel
+    Sk6812w,
+}
+
+impl StripType {
+    /// Whether the LEDs have a dedicated white channel
+    pub fn has_white(&self) -> bool {
+        matches!(self, StripType::Sk6812w)
+    }
+
+    /// The color order most commonly used by the type of strip
+    fn default_order(&self) -> ColorOrder {
+        match self {
+            StripType::Ws2811 => ColorOrder::Rgb,
+            StripType::Ws2812 | StripType::Sk6812 | StripType::Sk6812w => ColorOrder::Grb,
+        }
+    }
+}
+
+/// The order that the red, green, and blue channels are sent to the LEDs in. The white channel of
+/// RGBW strips is always sent last.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorOrder {
+    Rgb,
+    Rbg,
+    Grb,
+    Gbr,
+    Brg,
+    Bgr,
+}
+
+/// Where frames are written
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    /// A strip connected to the GPIO pins of a Raspberry Pi
+    Ws281x,
+    /// A virtual strip which can be shown in the terminal or a browser
+    Mock,
+    /// Nothing, frames are discarded
+    Null,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        // Only a Raspberry Pi can drive a strip directly
+        if cfg!(target_arch = "aarch64") {
+            Output::Ws281x
+        } else {
+            Output::Mock
+        }
+    }
+}
+
+/// A remote device that frames are sent to over UDP, such as a WLED node
+#[derive(Clone, Debug, Deserialize)]
+pub struct Destination {
+    /// The address and port of the device
+    pub address: SocketAddr,
+
+    /// The protocol the device receives frames with
+    #[serde(default)]
+    pub protocol: Protocol,
+
+    /// The index of the first LED sent to the device
+    pub start: u16,
+
+    /// The index after the last LED sent to the device
+    pub end: u16,
+
+    /// The first E1.31 universe to send to. Each universe holds 170 LEDs, and larger ranges
+    /// continue on the following universes.
+    #[serde(default = "default_universe")]
+    pub universe: u16,
+}
+
+fn default_universe() -> u16 {
+    1
+}
+
+/// The protocols frames can be sent over the network with
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Distributed Display Protocol, usually on port 4048
+    #[default]
+    Ddp,
+    /// Streaming ACN (sACN), usually on port 5568
+    E131,
+}
+
+/// Corrections applied to every color before it is sent to the strip
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    /// The exponent of the gamma curve, 1.0 disables gamma correction
+    pub gamma: f32,
+
+    /// How much each channel is scaled by to balance the white point of the LEDs
+    pub white_balance: WhiteBalance,
+
+    /// The color temperature in Kelvin to shift white towards
+    pub temperature: Option<u16>,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            gamma: 1.0,
+            white_balance: WhiteBalance::default(),
+            temperature: None,
+        }
+    }
+}
+
+/// The scaling factor of each channel in the range 0.0-1.0 inclusive
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WhiteBalance {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub w: f32,
+}
+
+impl Default for WhiteBalance {
+    fn default() -> Self {
+        WhiteBalance {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            w: 1.0,
+        }
+    }
+}
+
+/// The current drawn by the strip and the limit imposed by the power supply
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Power {
+    /// The most current the strip can draw in amps, limiting is disabled if not set
+    pub budget: Option<f32>,
+
+    /// The current drawn by each channel of an LED at full brightness in milliamps
+    pub milliamps: ChannelCurrent,
+
+    /// The current drawn by each LED while it is off in milliamps
+    pub idle: f32,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Power {
+            budget: None,
+            milliamps: ChannelCurrent::default(),
+            idle: 1.0,
+        }
+    }
+}
+
+/// The current drawn by each channel in milliamps
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ChannelCurrent {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub w: f32,
+}
+
+impl Default for ChannelCurrent {
+    fn default() -> Self {
+        ChannelCurrent {
+            r: 20.0,
+            g: 20.0,
+            b: 20.0,
+            w: 20.0,
+        }
+    }
+}
+
+/// How frames are written to the strip
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Render {
+    /// The most frames that will be written to the strip per second
+    pub max_fps: u16,
+
+    /// Whether to temporally dither colors that fall between the 8-bit values the LEDs support.
+    /// While dithering, frames are rendered continuously at the maximum frame rate.
+    pub dithering: bool,
+}
+
+impl Default for Render {
+    fn default() -> Self {
+        Render {
+            max_fps: 60,
+            dithering: false,
+        }
+    }
+}
+
+/// How the strip is shown by the mock output
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Mock {
+    /// Whether to draw the strip at the top of the terminal using truecolor ANSI escape codes
+    pub terminal: bool,
+
+    /// The number of LEDs drawn on each line of the terminal
+    pub columns: u16,
+
+    /// How rendered frames are captured to images
+    pub capture: Capture,
+
+    /// How the strip is previewed in a browser while in development mode
+    pub preview: Preview,
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Mock {
+            terminal: false,
+            columns: 75,
+            capture: Capture::default(),
+            preview: Preview::default(),
+        }
+    }
+}
+
+/// How the strip is previewed in a browser. The preview is only served in development mode.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Preview {
+    /// Whether to serve the preview
+    pub enabled: bool,
+
+    /// The host and port to serve the preview on
+    pub address: SocketAddr,
+
+    /// Where each LED is drawn on the page
+    pub layout: Layout,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Preview {
+            enabled: true,
+            address: SocketAddr::from(([127, 0, 0, 1], 30001)),
+            layout: Layout::Strip,
+        }
+    }
+}
+
+/// How the LEDs are arranged when previewing the strip. Positions are measured in the spacing
+/// between neighboring LEDs.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Layout {
+    /// A single straight line
+    #[default]
+    Strip,
+    /// Rows of LEDs, top to bottom
+    Grid {
+        /// The number of LEDs in each row
+        columns: u16,
+        /// Whether every other row runs right to left, as when a strip is folded back and forth
+        #[serde(default)]
+        serpentine: bool,
+    },
+    /// An explicit position for every LED
+    Custom {
+        /// The x and y coordinates of each LED, with y increasing downwards
+        points: Vec<[f64; 2]>,
+    },
+}
+
+impl Layout {
+    /// Get the position of each LED on a strip
+    pub fn points(&self, leds: usize) -> Vec<[f64; 2]> {
+        match self {
+            Layout::Strip => (0..leds).map(|i| [i as f64, 0.0]).collect(),
+            Layout::Grid {
+                columns,
+                serpentine,
+            } => {
+                let columns = *columns as usize;
+                (0..leds)
+                    .map(|i| {
+                        let (row, column) = (i / columns, i % columns);
+                        let column = match *serpentine && row % 2 == 1 {
+                            true => columns - 1 - column,
+                            false => column,
+                        };
+                        [column as f64, row as f64]
+                    })
+                    .collect()
+            }
+            Layout::Custom { points } => points.clone(),
+        }
+    }
 }
 
-impl From<RawConfig> for Config {
-    fn from(raw: RawConfig) -> Self {
-        Config {
+/// How rendered frames are captured to images. Captures can also be started over the API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Capture {
+    /// Whether to capture every frame from startup, saving them when the controller stops
+    pub enabled: bool,
+
+    /// The kind of image frames are saved as
+    pub format: CaptureFormat,
+
+    /// How many pixels wide and tall each LED is drawn as
+    pub scale: u16,
+
+    /// The most frames kept in memory for a single capture. Captures running since startup are
+    /// saved and continue in a new file once they reach this, while other captures stop early.
+    pub max_frames: u32,
+
+    /// Where captures are saved
+    pub directory: PathBuf,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Capture {
+            enabled: false,
+            format: CaptureFormat::Png,
+            scale: 1,
+            max_frames: 3600,
+            directory: PathBuf::from("./captures"),
+        }
+    }
+}
+
+/// The kinds of image frames can be captured as
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureFormat {
+    /// A still image with one row of LEDs for each frame, top to bottom
+    Png,
+    /// An animation with the same timing the frames were rendered at
+    Gif,
+}
+
+impl CaptureFormat {
+    /// The extension for files of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Gif => "gif",
+        }
+    }
+}
+
+/// What the strip shows when the controller starts
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Startup {
+    /// Show whatever was on the strip when the controller stopped. The state of the strip is saved
+    /// whenever it changes.
+    #[default]
+    Restore,
+    /// Start with the strip turned off
+    Off,
+    /// Always start with the same color, brightness, and animation
+    Fixed(FixedStartup),
+}
+
+/// The fixed contents of the strip on startup
+#[derive(Clone, Debug, Deserialize)]
+pub struct FixedStartup {
+    /// The color to fill the strip with
+    #[serde(default)]
+    pub color: StartupColor,
+
+    /// The brightness of the strip
+    #[serde(default = "full_brightness")]
+    pub brightness: u8,
+
+    /// The animation to run on the entire strip
+    pub animation: Option<String>,
+}
+
+/// A color with each channel in the range 0-255 inclusive. The white channel is only used by RGBW
+/// strips.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct StartupColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub w: Option<u8>,
+}
+
+fn full_brightness() -> u8 {
+    u8::MAX
+}
+
+/// How schedules are run
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Scheduler {
+    /// The timezone schedules run in, as a POSIX TZ string
+    #[serde(deserialize_with = "parse_timezone")]
+    pub timezone: TimeZone,
+
+    /// Where the strip is, needed for schedules relative to sunrise and sunset
+    pub location: Option<Location>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            timezone: TimeZone::UTC,
+            location: None,
+        }
+    }
+}
+
+/// How clips are recorded
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Clips {
+    /// The most frames a single recording keeps. Recordings stop early once they reach this.
+    pub max_frames: u32,
+}
+
+impl Default for Clips {
+    fn default() -> Self {
+        Clips { max_frames: 3600 }
+    }
+}
+
+/// A named range of LEDs on the strip
+#[derive(Clone, Debug)]
+pub struct Segment {
+    /// The name used to address the segment
+    pub name: String,
+
+    /// The index of the first LED in the segment
+    pub start: u16,
+
+    /// The index after the last LED in the segment
+    pub end: u16,
+}
+
+impl Segment {
+    /// The number of LEDs in the segment
+    pub fn length(&self) -> u16 {
+        self.end - self.start
+    }
+}
+
+impl TryFrom<RawConfig> for Config {
+    type Error = eyre::Report;
+
+    fn try_from(raw: RawConfig) -> eyre::Result<Self> {
+        let leds = raw.strip_density * raw.strip_length;
+
+        let mut segments = raw
+            .segments
+            .into_iter()
+            .map(|(name, segment)| Segment {
+                name,
+                start: segment.start,
+                end: segment.end,
+            })
+            .collect::<Vec<_>>();
+        segments.sort_by_key(|s| s.start);
+
+        for segment in &segments {
+            if segment.start >= segment.end {
+                return Err(eyre!(
+                    "segment {:?} must contain at least 1 LED",
+                    segment.name
+                ));
+            }
+            if segment.end > leds {
+                return Err(eyre!(
+                    "segment {:?} extends past the end of the strip ({} LEDs)",
+                    segment.name,
+                    leds
+                ));
+            }
+        }
+        for pair in segments.windows(2) {
+            if pair[0].end > pair[1].start {
+                return Err(eyre!(
+                    "segments {:?} and {:?} overlap",
+                    pair[0].name,
+                    pair[1].name
+                ));
+            }
+        }
+
+        for destination in &raw.network {
+            if destination.start >= destination.end || destination.end > leds {
+                return Err(eyre!(
+                    "network destination {} must contain between 1 and {} LEDs",
+                    destination.address,
+                    leds
+                ));
+            }
+            if destination.protocol == Protocol::E131 {
+                // Each universe holds 170 LEDs
+                let universes = (destination.start..destination.end).step_by(170).count();
+                let last = destination.universe as usize + universes - 1;
+                if destination.universe == 0 || last > 63999 {
+                    return Err(eyre!(
+                        "network destination {} must use universes between 1 and 63999",
+                        destination.address
+                    ));
+                }
+            }
+        }
+
+        let calibration = raw.calibration;
+        if calibration.gamma <= 0.0 {
+            return Err(eyre!("calibration gamma must be greater than 0"));
+        }
+        let WhiteBalance { r, g, b, w } = &calibration.white_balance;
+        if [r, g, b, w].into_iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return Err(eyre!("white balance must be between 0.0 and 1.0"));
+        }
+        if let Some(temperature) = calibration.temperature {
+            if !(1000..=40000).contains(&temperature) {
+                return Err(eyre!("color temperature must be between 1000K and 40000K"));
+            }
+        }
+
+        let power = raw.power;
+        if let Some(budget) = power.budget {
+            let idle = power.idle * leds as f32 / 1000.0;
+            if budget <= idle {
+                return Err(eyre!(
+                    "power budget must be greater than the idle draw of the strip ({}A)",
+                    idle
+                ));
+            }
+        }
+
+        if raw.render.max_fps == 0 {
+            return Err(eyre!("maximum frame rate must be greater than 0"));
+        }
+
+        if raw.clips.max_frames == 0 {
+            return Err(eyre!("clip max frames must be greater than 0"));
+        }
+
+        if raw.mock.columns == 0 {
+            return Err(eyre!("mock columns must be greater than 0"));
+        }
+        if raw.mock.capture.scale == 0 {
+            return Err(eyre!("capture scale must be greater than 0"));
+        }
+        if leds as u32 * raw.mock.capture.scale as u32 > u16::MAX as u32 {
+            return Err(eyre!("captures can be at most {} pixels wide", u16::MAX));
+        }
+        if raw.mock.capture.max_frames == 0 {
+            return Err(eyre!("capture max frames must be greater than 0"));
+        }
+        match &raw.mock.preview.layout {
+            Layout::Strip => {}
+            Layout::Grid { columns, .. } => {
+                if *columns == 0 {
+                    return Err(eyre!("preview grid columns must be greater than 0"));
+                }
+            }
+            Layout::Custom { points } => {
+                if points.len() != leds as usize {
+                    return Err(eyre!(
+                        "preview layout must have a point for each of the {} LEDs",
+                        leds
+                    ));
+                }
+                if points.iter().flatten().any(|c| !c.is_finite()) {
+                    return Err(eyre!("preview layout points must be finite"));
+                }
+            }
+        }
+
+        if let Some(location) = raw.scheduler.location {
+            if !(-90.0..=90.0).contains(&location.latitude) {
+                return Err(eyre!("latitude must be between -90 and 90 degrees"));
+            }
+            if !(-180.0..=180.0).contains(&location.longitude) {
+                return Err(eyre!("longitude must be between -180 and 180 degrees"));
+            }
+        }
+
+        Ok(Config {
             address: raw.controller.address,
             animations_path: raw.controller.animations,
-            leds: raw.strip_density * raw.strip_length,
+            leds,
+            strip_density: raw.strip_density,
+            strip_length: raw.strip_length,
+            strip_type: raw.strip_type,
+            color_order: raw
+                .color_order
+                .unwrap_or_else(|| raw.strip_type.default_order()),
+            output: raw.output,
+            network: raw.network,
+            calibration,
+            power,
+            render: raw.render,
+            startup: raw.startup,
+            scheduler: raw.scheduler,
+            clips: raw.clips,
+            mock: raw.mock,
             log_level: raw.log_level,
             development: raw.development,
-        }
+            segments,
+        })
     }
 }
 
@@ -41,9 +701,58 @@ impl Config {
     pub async fn load() -> eyre::Result<Config> {
         let path = find_config_path()?;
         let contents = fs::read(&path).await.wrap_err("unable to open file")?;
+        Config::parse(&contents)
+    }
+
+    /// Parse and validate the configuration from the contents of a file
+    pub fn parse(contents: &[u8]) -> eyre::Result<Config> {
+        let raw = toml::from_slice::<RawConfig>(contents).wrap_err("TOML parsing failed")?;
+        raw.try_into().wrap_err("invalid configuration")
+    }
+
+    /// A minimal configuration for a short strip using the mock output. Each top-level key in the
+    /// overrides replaces the default.
+    #[cfg(test)]
+    pub fn test(overrides: &str) -> Config {
+        let mut config = toml::toml! {
+            log_level = "info"
+            strip_density = 4
+            strip_length = 1
+            output = "mock"
+            development = false
+
+            [controller]
+            address = "127.0.0.1:30000"
+            animations = "/tmp/animations"
+        };
+
+        let overrides = toml::from_str::<toml::value::Table>(overrides).unwrap();
+        let table = config.as_table_mut().unwrap();
+        for (key, value) in overrides {
+            table.insert(key, value);
+        }
+
+        Config::parse(toml::to_string(&config).unwrap().as_bytes()).unwrap()
+    }
+
+    /// Where the state of the strip is saved, next to the animations directory
+    pub fn state_path(&self) -> PathBuf {
+        self.animations_path.with_file_name("state.toml")
+    }
+
+    /// Where presets are saved, next to the animations directory
+    pub fn presets_path(&self) -> PathBuf {
+        self.animations_path.with_file_name("presets.toml")
+    }
+
+    /// Where recorded clips are saved, next to the animations directory
+    pub fn clips_path(&self) -> PathBuf {
+        self.animations_path.with_file_name("clips")
+    }
 
-        let raw = toml::from_slice::<RawConfig>(&contents).wrap_err("TOML parsing failed")?;
-        return Ok(raw.into());
+    /// Where schedules are saved, next to the animations directory
+    pub fn schedules_path(&self) -> PathBuf {
+        self.animations_path.with_file_name("schedules.toml")
     }
 }
 
@@ -73,8 +782,31 @@ struct RawConfig {
     log_level: Level,
     strip_density: u16,
     strip_length: u16,
+    #[serde(default)]
+    strip_type: StripType,
+    color_order: Option<ColorOrder>,
+    #[serde(default)]
+    output: Output,
+    #[serde(default)]
+    network: Vec<Destination>,
+    #[serde(default)]
+    calibration: Calibration,
+    #[serde(default)]
+    power: Power,
+    #[serde(default)]
+    render: Render,
+    #[serde(default)]
+    startup: Startup,
+    #[serde(default)]
+    scheduler: Scheduler,
+    #[serde(default)]
+    clips: Clips,
+    #[serde(default)]
+    mock: Mock,
     development: bool,
     controller: RawControllerConfig,
+    #[serde(default)]
+    segments: BTreeMap<String, RawSegment>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -83,6 +815,12 @@ struct RawControllerConfig {
     animations: PathBuf,
 }
 
+#[derive(Debug, Deserialize)]
+struct RawSegment {
+    start: u16,
+    end: u16,
+}
+
 fn parse_level<'de, D>(deserializer: D) -> Result<Level, D::Error>
 where
     D: Deserializer<'de>,
@@ -90,3 +828,11 @@ where
     let s = String::deserialize(deserializer)?;
     Level::from_str(&s).map_err(Error::custom)
 }
+
+fn parse_timezone<'de, D>(deserializer: D) -> Result<TimeZone, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    TimeZone::from_str(&s).map_err(Error::custom)
+}
@@ -1,4 +1,5 @@
 use rs_ws281x::WS2811Error;
+use std::io;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,10 +12,38 @@ pub enum PixelsError {
     Permissions,
     #[error("out of memory")]
     OutOfMemory,
+    #[error("failed to open network socket: {0}")]
+    Network(io::Error),
     #[error("an unknown error occurred")]
     Other,
 }
 
+#[derive(Debug, Error)]
+#[error("unknown segment {0:?}")]
+pub struct UnknownSegment(pub String);
+
+#[derive(Debug, Error)]
+#[error("unknown preset {0:?}")]
+pub struct UnknownPreset(pub String);
+
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error(transparent)]
+    UnknownSegment(#[from] UnknownSegment),
+    #[error(transparent)]
+    UnknownPreset(#[from] UnknownPreset),
+}
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("failed to access file: {0}")]
+    IO(#[from] io::Error),
+    #[error("failed to parse state: {0}")]
+    Deserialization(#[from] toml::de::Error),
+    #[error("failed to serialize state: {0}")]
+    Serialization(#[from] toml::ser::Error),
+}
+
 impl From<WS2811Error> for PixelsError {
     fn from(e: WS2811Error) -> Self {
         match e {
@@ -31,3 +60,47 @@ impl From<WS2811Error> for PixelsError {
         }
     }
 }
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("frames can only be captured by the mock strip")]
+    NotSupported,
+    #[error("pixel manager is not running")]
+    Unavailable,
+    #[error("frames are already being captured")]
+    InProgress,
+    #[error("no frames were rendered while capturing")]
+    Empty,
+    #[error("failed to write capture: {0}")]
+    IO(#[from] io::Error),
+    #[error("failed to encode capture: {0}")]
+    Encoding(String),
+}
+
+#[derive(Debug, Error)]
+pub enum ClipError {
+    #[error("pixel manager is not running")]
+    Unavailable,
+    #[error("a clip is already being recorded")]
+    InProgress,
+    #[error("no frames were rendered while recording")]
+    Empty,
+    #[error("unknown clip {0:?}")]
+    UnknownClip(String),
+    #[error(
+        "invalid clip name {0:?}, must only contain letters, numbers, dashes, and underscores"
+    )]
+    InvalidName(String),
+    #[error("invalid clip: {0}")]
+    Invalid(&'static str),
+    #[error("failed to access clip: {0}")]
+    IO(#[from] io::Error),
+}
+
+#[derive(Debug, Error)]
+#[error("invalid timezone: {0}")]
+pub struct InvalidTimeZone(pub &'static str);
+
+#[derive(Debug, Error)]
+#[error("invalid cron expression: {0}")]
+pub struct InvalidCron(pub String);
@@ -0,0 +1,186 @@
+use crate::{
+    config::{Capture, CaptureFormat},
+    errors::CaptureError,
+};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// The shortest time a frame of a GIF can be shown for. Many viewers slow down frames which are
+/// shorter than this, so frames rendered faster are combined instead.
+const MIN_GIF_DELAY: Duration = Duration::from_millis(20);
+
+/// Collects the frames written to the strip and saves them as an image
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    format: CaptureFormat,
+    scale: usize,
+    max_frames: usize,
+    directory: PathBuf,
+    frames: Vec<(Instant, Vec<[u8; 3]>)>,
+}
+
+impl Recorder {
+    /// Start recording frames to save in a format
+    pub fn new(config: &Capture, format: CaptureFormat) -> Self {
+        Recorder {
+            format,
+            scale: config.scale as usize,
+            max_frames: config.max_frames as usize,
+            directory: config.directory.clone(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Add a frame of red, green, and blue colors. Frames are dropped once the recorder is full.
+    pub fn record(&mut self, colors: Vec<[u8; 3]>) {
+        if self.is_full() {
+            return;
+        }
+
+        self.frames.push((Instant::now(), colors));
+        if self.is_full() {
+            warn!(
+                frames = self.max_frames,
+                "capture reached the maximum number of frames"
+            );
+        }
+    }
+
+    /// Whether the maximum number of frames has been recorded
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.max_frames
+    }
+
+    /// The format frames are saved in
+    pub fn format(&self) -> CaptureFormat {
+        self.format
+    }
+
+    /// Write the recorded frames to a new file in the capture directory, returning its path
+    pub fn save(self) -> Result<PathBuf, CaptureError> {
+        if self.frames.is_empty() {
+            return Err(CaptureError::Empty);
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!("capture-{}.{}", timestamp, self.format.extension());
+        let path = self.directory.join(name);
+
+        fs::create_dir_all(&self.directory)?;
+        let mut file = BufWriter::new(File::create(&path)?);
+        match self.format {
+            CaptureFormat::Png => self.png(&mut file)?,
+            CaptureFormat::Gif => self.gif(&mut file)?,
+        }
+        file.flush()?;
+
+        Ok(path)
+    }
+
+    /// The number of pixels in each row of the image
+    fn width(&self) -> usize {
+        self.frames[0].1.len() * self.scale
+    }
+
+    /// The pixels of a frame, with each LED scaled up to a square
+    fn pixels(&self, colors: &[[u8; 3]]) -> Vec<u8> {
+        let row = colors
+            .iter()
+            .flat_map(|color| color.repeat(self.scale))
+            .collect::<Vec<_>>();
+        row.repeat(self.scale)
+    }
+
+    /// Encode the frames as a still image with one row of LEDs for each frame
+    fn png<W: Write>(&self, writer: W) -> Result<(), CaptureError> {
+        let height = self.frames.len() * self.scale;
+        let mut encoder = png::Encoder::new(writer, self.width() as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data = self
+            .frames
+            .iter()
+            .flat_map(|(_, colors)| self.pixels(colors))
+            .collect::<Vec<_>>();
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Encode the frames as an animation with the same timing they were rendered at
+    fn gif<W: Write>(&self, writer: W) -> Result<(), CaptureError> {
+        let (width, height) = (self.width() as u16, self.scale as u16);
+        let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        // Combine frames which would be shown too briefly, keeping the latest colors
+        let mut frames: Vec<(Instant, &[[u8; 3]])> = Vec::new();
+        for (time, colors) in &self.frames {
+            match frames.last_mut() {
+                Some((start, last)) if *time - *start < MIN_GIF_DELAY => *last = colors,
+                _ => frames.push((*time, colors)),
+            }
+        }
+
+        // The last frame is shown until the capture ended
+        let ends = frames
+            .iter()
+            .skip(1)
+            .map(|(time, _)| *time)
+            .chain([Instant::now()]);
+        for ((start, colors), end) in frames.iter().zip(ends) {
+            let mut frame = gif::Frame::from_rgb_speed(width, height, &self.pixels(colors), 10);
+            let delay = (end - *start).as_millis() / 10;
+            frame.delay = delay.clamp(1, u16::MAX as u128) as u16;
+            encoder.write_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<png::EncodingError> for CaptureError {
+    fn from(e: png::EncodingError) -> Self {
+        CaptureError::Encoding(e.to_string())
+    }
+}
+
+impl From<gif::EncodingError> for CaptureError {
+    fn from(e: gif::EncodingError) -> Self {
+        CaptureError::Encoding(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_at_max_frames() {
+        let config = Capture {
+            max_frames: 3,
+            ..Capture::default()
+        };
+        let mut recorder = Recorder::new(&config, CaptureFormat::Png);
+
+        for i in 0..5 {
+            assert_eq!(recorder.is_full(), i >= 3);
+            recorder.record(vec![[i; 3]; 2]);
+        }
+
+        // The earliest frames are kept
+        assert_eq!(recorder.frames.len(), 3);
+        assert_eq!(recorder.frames[2].1, [[2; 3]; 2]);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Output, RawColor};
+use crate::{
+    config::{self, CaptureFormat, ColorOrder, Config},
+    errors::{CaptureError, PixelsError},
+};
+use std::mem;
+use tokio::task;
+use tracing::{debug, error, info};
+
+mod capture;
+mod preview;
+mod terminal;
+
+pub(crate) use capture::Recorder;
+use preview::Preview;
+use terminal::Terminal;
+
+/// A virtual strip for running without any LEDs. Frames are logged, and can be drawn in the
+/// terminal, previewed in a browser, or captured to images.
+#[derive(Debug)]
+pub struct Mock {
+    white: bool,
+    order: ColorOrder,
+    config: config::Mock,
+    terminal: Option<Terminal>,
+    /// Captures every frame since startup when enabled in the configuration
+    startup: Option<Recorder>,
+    /// Captures frames until finished when started over the API
+    capture: Option<Recorder>,
+    preview: Option<Preview>,
+}
+
+impl Mock {
+    /// Set up the ways of showing the strip enabled by the configuration
+    pub fn new(config: &Config) -> Self {
+        let leds = config.leds as usize;
+        let mock = &config.mock;
+
+        let terminal = mock
+            .terminal
+            .then(|| Terminal::new(leds, mock.columns as usize));
+        let startup = mock
+            .capture
+            .enabled
+            .then(|| Recorder::new(&mock.capture, mock.capture.format));
+
+        // The preview is only a development aid, so the strip still works without it
+        let preview = match config.development && mock.preview.enabled {
+            true => match Preview::start(&mock.preview, leds) {
+                Ok(preview) => Some(preview),
+                Err(err) => {
+                    error!(%err, address = %mock.preview.address, "failed to serve preview");
+                    None
+                }
+            },
+            false => None,
+        };
+
+        Mock {
+            white: config.strip_type.has_white(),
+            order: config.color_order,
+            config: mock.clone(),
+            terminal,
+            startup,
+            capture: None,
+            preview,
+        }
+    }
+
+    /// The bytes that would be sent to the strip, reproducing the channel ordering performed by
+    /// rpi_ws281x
+    pub(crate) fn wire(&self, leds: &[RawColor]) -> Vec<u8> {
+        // The offset of each channel within a raw color in the order they are sent to the LEDs.
+        // This mirrors the strip type definitions from rpi_ws281x.
+        let shifts: &[u32] = match self.white {
+            true => &[16, 8, 0, 24],
+            false => &[16, 8, 0],
+        };
+
+        leds.iter()
+            .flat_map(|led| {
+                let value = u32::from_le_bytes(*led);
+                shifts
+                    .iter()
+                    .map(move |shift| ((value >> shift) & 0xff) as u8)
+            })
+            .collect()
+    }
+}
+
+impl Output for Mock {
+    fn render(&mut self, leds: &[RawColor]) -> Result<(), PixelsError> {
+        debug!(?leds, wire = ?self.wire(leds), "current strip state");
+
+        let recording = self.startup.is_some() || self.capture.is_some();
+        if self.terminal.is_some() || self.preview.is_some() || recording {
+            let colors = super::rgb(leds, self.order);
+            if let Some(terminal) = &self.terminal {
+                terminal.draw(&colors);
+            }
+            if let Some(preview) = &self.preview {
+                preview.send(colors.clone());
+            }
+            if let Some(recorder) = &mut self.capture {
+                recorder.record(colors.clone());
+            }
+            if let Some(recorder) = &mut self.startup {
+                recorder.record(colors);
+            }
+        }
+
+        // The capture running since startup is saved each time it fills up so it doesn't keep
+        // growing. Encoding takes a while, so it happens off the pixel manager's thread.
+        if let Some(recorder) = self.startup.as_mut().filter(|r| r.is_full()) {
+            let next = Recorder::new(&self.config.capture, recorder.format());
+            let full = mem::replace(recorder, next);
+            task::spawn_blocking(move || match full.save() {
+                Ok(path) => info!(path = %path.display(), "saved capture"),
+                Err(err) => error!(%err, "failed to save capture"),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn start_capture(&mut self, format: CaptureFormat) -> Result<(), CaptureError> {
+        if self.capture.is_some() {
+            return Err(CaptureError::InProgress);
+        }
+
+        self.capture = Some(Recorder::new(&self.config.capture, format));
+        Ok(())
+    }
+
+    fn finish_capture(&mut self) -> Result<Recorder, CaptureError> {
+        self.capture.take().ok_or(CaptureError::Empty)
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        // Save anything captured since startup
+        if let Some(recorder) = self.startup.take() {
+            match recorder.save() {
+                Ok(path) => info!(path = %path.display(), "saved capture"),
+                Err(err) => error!(%err, "failed to save capture"),
+            }
+        }
+    }
+}
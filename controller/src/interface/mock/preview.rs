@@ -0,0 +1,95 @@
+use crate::config::Preview as Config;
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::{Html, Response},
+    routing::get,
+    BoxError, Extension, Router, Server,
+};
+use std::fmt::Write as _;
+use tokio::{sync::watch, task};
+use tracing::{error, info, info_span, Instrument};
+
+/// The page which draws the strip, with a placeholder for the position of each LED
+const PAGE: &str = include_str!("preview.html");
+
+/// The colors most recently written to the strip
+type Frames = watch::Receiver<Vec<[u8; 3]>>;
+
+/// Serves a page which shows the strip live in a browser. Frames are streamed to the page over a
+/// WebSocket as the red, green, and blue channels of each LED. The server stops once the preview
+/// is dropped.
+#[derive(Debug)]
+pub(crate) struct Preview {
+    frames: watch::Sender<Vec<[u8; 3]>>,
+}
+
+impl Preview {
+    /// Start serving the preview for a strip of LEDs
+    pub fn start(config: &Config, leds: usize) -> Result<Self, BoxError> {
+        let mut points = String::new();
+        for [x, y] in config.layout.points(leds) {
+            let _ = write!(points, "[{x},{y}],");
+        }
+        let page = PAGE.replace("/* POINTS */", &points);
+
+        let (tx, rx) = watch::channel(vec![[0, 0, 0]; leds]);
+        let mut closed = rx.clone();
+
+        let app = Router::new()
+            .route("/", get(move || async move { Html(page) }))
+            .route("/frames", get(upgrade))
+            .layer(Extension(rx));
+        let server = Server::try_bind(&config.address)?
+            .serve(app.into_make_service())
+            .with_graceful_shutdown(async move { while closed.changed().await.is_ok() {} });
+
+        info!(address = %config.address, "serving preview");
+        task::spawn(
+            async move {
+                if let Err(err) = server.await {
+                    error!(%err, "preview server failed");
+                }
+            }
+            .instrument(info_span!("preview")),
+        );
+
+        Ok(Preview { frames: tx })
+    }
+
+    /// Send the colors being shown to every open page
+    pub fn send(&self, colors: Vec<[u8; 3]>) {
+        self.frames.send_replace(colors);
+    }
+}
+
+/// Start streaming frames to a page
+async fn upgrade(upgrade: WebSocketUpgrade, Extension(frames): Extension<Frames>) -> Response {
+    upgrade.on_upgrade(|socket| stream(socket, frames))
+}
+
+/// Send each new frame to the page until either side closes
+async fn stream(mut socket: WebSocket, mut frames: Frames) {
+    let frame = frames.borrow_and_update().concat();
+    if socket.send(Message::Binary(frame)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            result = frames.changed() => {
+                if result.is_err() {
+                    break;
+                }
+
+                let frame = frames.borrow_and_update().concat();
+                if socket.send(Message::Binary(frame)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
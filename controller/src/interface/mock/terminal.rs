@@ -0,0 +1,49 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+/// Draws the strip at the top of the terminal as truecolor blocks. The lines below the strip are
+/// set up as a scrolling region so that logs can keep being written underneath it.
+#[derive(Debug)]
+pub(crate) struct Terminal {
+    columns: usize,
+}
+
+impl Terminal {
+    /// Reserve space at the top of the terminal for a strip of LEDs
+    pub fn new(leds: usize, columns: usize) -> Self {
+        let rows = (0..leds).step_by(columns).count();
+
+        // Clear the screen and restrict scrolling to below the strip and a blank line
+        print!("\x1b[2J\x1b[{};r\x1b[{};1H", rows + 2, rows + 2);
+        let _ = io::stdout().flush();
+
+        Terminal { columns }
+    }
+
+    /// Redraw the strip in place from a set of red, green, and blue colors
+    pub fn draw(&self, colors: &[[u8; 3]]) {
+        // Save the cursor and move to the top left corner
+        let mut output = String::from("\x1b7\x1b[1;1H");
+        for row in colors.chunks(self.columns) {
+            for [r, g, b] in row {
+                let _ = write!(output, "\x1b[38;2;{r};{g};{b}m\u{2588}");
+            }
+            output.push_str("\x1b[0m\x1b[K\r\n");
+        }
+        output.push_str("\x1b8");
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(output.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Restore scrolling for the whole screen without moving the cursor
+        print!("\x1b7\x1b[r\x1b8");
+        let _ = io::stdout().flush();
+    }
+}
@@ -1,8 +1,114 @@
-#[cfg(not(target_arch = "aarch64"))]
+use crate::{
+    config::{self, CaptureFormat, ColorOrder, Config},
+    errors::{CaptureError, PixelsError},
+};
 mod mock;
+mod network;
+mod null;
+mod ws281x;
 
-#[cfg(target_arch = "aarch64")]
-pub use rs_ws281x::{ChannelBuilder, Controller, ControllerBuilder, StripType};
+pub use mock::Mock;
+pub(crate) use mock::Recorder;
+pub use network::Network;
+pub use null::Null;
+pub use ws281x::Ws281x;
 
-#[cfg(not(target_arch = "aarch64"))]
-pub use mock::*;
+/// The channels of an LED in the order they are sent to the strip, packed into a little-endian
+/// word as rpi_ws281x expects
+pub type RawColor = [u8; 4];
+
+/// Somewhere frames are written to. Outputs are driven from the pixel manager's thread, so they
+/// don't need to be sendable between threads.
+pub trait Output {
+    /// Write a frame of colors, one for each LED, to the strip
+    fn render(&mut self, leds: &[RawColor]) -> Result<(), PixelsError>;
+
+    /// Start capturing the frames that are rendered
+    fn start_capture(&mut self, _format: CaptureFormat) -> Result<(), CaptureError> {
+        Err(CaptureError::NotSupported)
+    }
+
+    /// Stop capturing frames, handing back the ones which were rendered so they can be saved
+    fn finish_capture(&mut self) -> Result<Recorder, CaptureError> {
+        Err(CaptureError::NotSupported)
+    }
+}
+
+/// Connect to the output chosen by the configuration, along with any remote devices on the
+/// network
+pub fn connect(config: &Config) -> Result<Box<dyn Output>, PixelsError> {
+    let local: Box<dyn Output> = match config.output {
+        config::Output::Ws281x => Box::new(Ws281x::new(config)?),
+        config::Output::Mock => Box::new(Mock::new(config)),
+        config::Output::Null => Box::new(Null),
+    };
+
+    if config.network.is_empty() {
+        Ok(local)
+    } else {
+        let network = Box::new(Network::new(config)?);
+        Ok(Box::new(Outputs(vec![local, network])))
+    }
+}
+
+/// Writes each frame to several outputs
+struct Outputs(Vec<Box<dyn Output>>);
+
+impl Output for Outputs {
+    fn render(&mut self, leds: &[RawColor]) -> Result<(), PixelsError> {
+        // Keep the other outputs going even if one of them fails
+        let mut result = Ok(());
+        for output in &mut self.0 {
+            if let Err(err) = output.render(leds) {
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    fn start_capture(&mut self, format: CaptureFormat) -> Result<(), CaptureError> {
+        self.0
+            .iter_mut()
+            .map(|output| output.start_capture(format))
+            .find(|result| !matches!(result, Err(CaptureError::NotSupported)))
+            .unwrap_or(Err(CaptureError::NotSupported))
+    }
+
+    fn finish_capture(&mut self) -> Result<Recorder, CaptureError> {
+        self.0
+            .iter_mut()
+            .map(|output| output.finish_capture())
+            .find(|result| !matches!(result, Err(CaptureError::NotSupported)))
+            .unwrap_or(Err(CaptureError::NotSupported))
+    }
+}
+
+/// The colors the LEDs would light up with, undoing the channel ordering. White is mixed into the
+/// other channels for anything that can't show it separately.
+fn rgb(leds: &[RawColor], order: ColorOrder) -> Vec<[u8; 3]> {
+    leds.iter()
+        .map(|&led| {
+            let [r, g, b, w] = unpack(led, order);
+            [
+                r.saturating_add(w),
+                g.saturating_add(w),
+                b.saturating_add(w),
+            ]
+        })
+        .collect()
+}
+
+/// Recover the red, green, blue, and white channels of a raw color, reversing the arrangement
+/// performed before colors are written to the strip
+pub(crate) fn unpack(raw: RawColor, order: ColorOrder) -> [u8; 4] {
+    let [third, second, first, w] = raw;
+    let [r, g, b] = match order {
+        ColorOrder::Rgb => [first, second, third],
+        ColorOrder::Rbg => [first, third, second],
+        ColorOrder::Grb => [second, first, third],
+        ColorOrder::Gbr => [third, first, second],
+        ColorOrder::Brg => [second, third, first],
+        ColorOrder::Bgr => [third, second, first],
+    };
+    [r, g, b, w]
+}
@@ -0,0 +1,330 @@
+use super::{Output, RawColor};
+use crate::{
+    config::{ColorOrder, Config, Destination, Protocol},
+    errors::PixelsError,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{SocketAddr, UdpSocket},
+    ops::Range,
+};
+use tracing::{info, warn};
+
+/// The most bytes of pixel data sent in a single DDP packet, chosen so packets fit in a standard
+/// Ethernet frame
+const DDP_MAX_DATA: usize = 1440;
+
+/// The number of LEDs in each E1.31 universe, using 510 of the 512 DMX channels
+const E131_LEDS_PER_UNIVERSE: usize = 170;
+
+/// The name this controller identifies itself with to E1.31 receivers
+const E131_SOURCE_NAME: &str = "lights";
+
+/// Sends frames to remote devices over UDP. Each device receives its range of LEDs as plain RGB,
+/// with any white channel mixed into the other colors.
+#[derive(Debug)]
+pub struct Network {
+    order: ColorOrder,
+    cid: [u8; 16],
+    remotes: Vec<Remote>,
+}
+
+/// A device frames are sent to
+#[derive(Debug)]
+struct Remote {
+    socket: UdpSocket,
+    address: SocketAddr,
+    protocol: Protocol,
+    range: Range<usize>,
+    universe: u16,
+    sequence: u8,
+    failing: bool,
+}
+
+impl Network {
+    /// Open a socket for each of the configured destinations
+    pub fn new(config: &Config) -> Result<Self, PixelsError> {
+        let remotes = config
+            .network
+            .iter()
+            .map(Remote::new)
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(PixelsError::Network)?;
+
+        // Receivers tell sources apart by a unique id, which only needs to last until a restart
+        let mut cid = [0; 16];
+        for half in cid.chunks_mut(8) {
+            half.copy_from_slice(&RandomState::new().build_hasher().finish().to_be_bytes());
+        }
+
+        Ok(Network {
+            order: config.color_order,
+            cid,
+            remotes,
+        })
+    }
+}
+
+impl Output for Network {
+    fn render(&mut self, leds: &[RawColor]) -> Result<(), PixelsError> {
+        let colors = super::rgb(leds, self.order);
+        for remote in &mut self.remotes {
+            let data = colors[remote.range.clone()].concat();
+            let result = match remote.protocol {
+                Protocol::Ddp => remote.send_ddp(&data),
+                Protocol::E131 => remote.send_e131(&data, &self.cid),
+            };
+
+            // Only report when a device starts or stops failing rather than on every frame
+            match result {
+                Ok(()) if remote.failing => {
+                    info!(address = %remote.address, "sending frames again");
+                    remote.failing = false;
+                }
+                Err(err) if !remote.failing => {
+                    warn!(%err, address = %remote.address, "failed to send frame");
+                    remote.failing = true;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Remote {
+    fn new(destination: &Destination) -> io::Result<Self> {
+        let local: SocketAddr = match destination.address {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(destination.address)?;
+
+        Ok(Remote {
+            socket,
+            address: destination.address,
+            protocol: destination.protocol,
+            range: destination.start as usize..destination.end as usize,
+            universe: destination.universe,
+            sequence: 0,
+            failing: false,
+        })
+    }
+
+    /// Send a frame using DDP, splitting it into as many packets as needed. The last packet tells
+    /// the device to show the frame.
+    fn send_ddp(&mut self, data: &[u8]) -> io::Result<()> {
+        // Sequence numbers cycle through 1 to 15, since 0 means they aren't used
+        self.sequence = self.sequence % 15 + 1;
+
+        let chunks = data.chunks(DDP_MAX_DATA);
+        let count = chunks.len();
+        for (i, chunk) in chunks.enumerate() {
+            let offset = (i * DDP_MAX_DATA) as u32;
+            let push = if i + 1 == count { 0x01 } else { 0x00 };
+
+            let mut packet = Vec::with_capacity(10 + chunk.len());
+            packet.push(0x40 | push); // Version 1
+            packet.push(self.sequence);
+            packet.push(0x0b); // RGB with 8 bits per channel
+            packet.push(0x01); // The default output of the device
+            packet.extend_from_slice(&offset.to_be_bytes());
+            packet.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+            packet.extend_from_slice(chunk);
+            self.socket.send(&packet)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a frame using E1.31, with one packet for each universe the frame spans
+    fn send_e131(&mut self, data: &[u8], cid: &[u8; 16]) -> io::Result<()> {
+        for (i, channels) in data.chunks(E131_LEDS_PER_UNIVERSE * 3).enumerate() {
+            self.sequence = self.sequence.wrapping_add(1);
+
+            let universe = self.universe + i as u16;
+            let length = 126 + channels.len() as u16;
+            let mut packet = Vec::with_capacity(length as usize);
+
+            // Root layer
+            packet.extend_from_slice(&[0x00, 0x10, 0x00, 0x00]);
+            packet.extend_from_slice(b"ASC-E1.17\0\0\0");
+            packet.extend_from_slice(&(0x7000 | (length - 16)).to_be_bytes());
+            packet.extend_from_slice(&4u32.to_be_bytes());
+            packet.extend_from_slice(cid);
+
+            // Framing layer
+            let mut name = [0; 64];
+            name[..E131_SOURCE_NAME.len()].copy_from_slice(E131_SOURCE_NAME.as_bytes());
+            packet.extend_from_slice(&(0x7000 | (length - 38)).to_be_bytes());
+            packet.extend_from_slice(&2u32.to_be_bytes());
+            packet.extend_from_slice(&name);
+            packet.push(100); // Priority
+            packet.extend_from_slice(&[0x00, 0x00]); // Synchronization universe
+            packet.push(self.sequence);
+            packet.push(0x00); // Options
+            packet.extend_from_slice(&universe.to_be_bytes());
+
+            // DMP layer
+            packet.extend_from_slice(&(0x7000 | (length - 115)).to_be_bytes());
+            packet.extend_from_slice(&[0x02, 0xa1, 0x00, 0x00, 0x00, 0x01]);
+            packet.extend_from_slice(&(channels.len() as u16 + 1).to_be_bytes());
+            packet.push(0x00); // DMX start code
+            packet.extend_from_slice(channels);
+
+            self.socket.send(&packet)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Bind a listener for the output to send to
+    fn listener() -> UdpSocket {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        socket
+    }
+
+    /// Create an output sending every LED to the listener
+    fn network(listener: &UdpSocket, protocol: Protocol, leds: u16, universe: u16) -> Network {
+        let destination = Destination {
+            address: listener.local_addr().unwrap(),
+            protocol,
+            start: 0,
+            end: leds,
+            universe,
+        };
+
+        Network {
+            order: ColorOrder::Rgb,
+            cid: [7; 16],
+            remotes: vec![Remote::new(&destination).unwrap()],
+        }
+    }
+
+    /// A frame where each LED has a distinct color, packed as it would be for an RGB strip
+    fn frame(leds: u16) -> (Vec<RawColor>, Vec<u8>) {
+        let colors = (0..leds)
+            .map(|i| [i as u8, (i >> 8) as u8, 0xaa])
+            .collect::<Vec<_>>();
+        let raw = colors.iter().map(|&[r, g, b]| [b, g, r, 0]).collect();
+        (raw, colors.concat())
+    }
+
+    fn receive(listener: &UdpSocket) -> Vec<u8> {
+        let mut buffer = [0; 2048];
+        let length = listener.recv(&mut buffer).unwrap();
+        buffer[..length].to_vec()
+    }
+
+    #[test]
+    fn ddp_single_packet() {
+        let listener = listener();
+        let mut network = network(&listener, Protocol::Ddp, 2, 1);
+        let (leds, data) = frame(2);
+
+        network.render(&leds).unwrap();
+        let packet = receive(&listener);
+
+        assert_eq!(packet[0], 0x41, "version 1 with push");
+        assert_eq!(packet[1], 1, "sequence");
+        assert_eq!(packet[2], 0x0b, "RGB with 8 bits per channel");
+        assert_eq!(packet[3], 0x01, "default output");
+        assert_eq!(&packet[4..8], &[0, 0, 0, 0], "offset");
+        assert_eq!(&packet[8..10], &[0, 6], "length");
+        assert_eq!(&packet[10..], data.as_slice());
+
+        // The sequence number moves on with each frame
+        network.render(&leds).unwrap();
+        assert_eq!(receive(&listener)[1], 2);
+    }
+
+    #[test]
+    fn ddp_splits_large_frames() {
+        let listener = listener();
+        let mut network = network(&listener, Protocol::Ddp, 500, 1);
+        let (leds, data) = frame(500);
+
+        network.render(&leds).unwrap();
+        let first = receive(&listener);
+        let second = receive(&listener);
+
+        // Only the last packet tells the device to show the frame
+        assert_eq!(first[0], 0x40);
+        assert_eq!(second[0], 0x41);
+        assert_eq!(first[1], second[1]);
+
+        assert_eq!(&first[4..8], &0u32.to_be_bytes());
+        assert_eq!(&first[8..10], &(DDP_MAX_DATA as u16).to_be_bytes());
+        assert_eq!(&second[4..8], &(DDP_MAX_DATA as u32).to_be_bytes());
+        assert_eq!(&second[8..10], &60u16.to_be_bytes());
+        assert_eq!([&first[10..], &second[10..]].concat(), data);
+    }
+
+    #[test]
+    fn e131_headers() {
+        let listener = listener();
+        let mut network = network(&listener, Protocol::E131, 2, 3);
+        let (leds, data) = frame(2);
+
+        network.render(&leds).unwrap();
+        let packet = receive(&listener);
+
+        assert_eq!(packet.len(), 126 + 6);
+        assert_eq!(
+            &packet[0..4],
+            &[0x00, 0x10, 0x00, 0x00],
+            "preamble and postamble"
+        );
+        assert_eq!(&packet[4..16], b"ASC-E1.17\0\0\0", "ACN packet identifier");
+        assert_eq!(&packet[22..38], &[7; 16], "CID");
+        assert_eq!(
+            &packet[44..44 + E131_SOURCE_NAME.len()],
+            E131_SOURCE_NAME.as_bytes()
+        );
+        assert_eq!(&packet[113..115], &3u16.to_be_bytes(), "universe");
+        assert_eq!(&packet[123..125], &7u16.to_be_bytes(), "property count");
+        assert_eq!(packet[125], 0x00, "DMX start code");
+        assert_eq!(&packet[126..], data.as_slice());
+
+        // The sequence number moves on with each packet
+        let sequence = packet[111];
+        network.render(&leds).unwrap();
+        assert_eq!(receive(&listener)[111], sequence.wrapping_add(1));
+    }
+
+    #[test]
+    fn e131_splits_across_universes() {
+        let listener = listener();
+        let mut network = network(&listener, Protocol::E131, 200, 5);
+        let (leds, data) = frame(200);
+
+        network.render(&leds).unwrap();
+        let first = receive(&listener);
+        let second = receive(&listener);
+
+        assert_eq!(&first[113..115], &5u16.to_be_bytes());
+        assert_eq!(&second[113..115], &6u16.to_be_bytes());
+        assert_eq!(second[111], first[111].wrapping_add(1));
+
+        assert_eq!(first.len() - 126, E131_LEDS_PER_UNIVERSE * 3);
+        assert_eq!(second.len() - 126, 30 * 3);
+        assert_eq!(&first[123..125], &511u16.to_be_bytes());
+        assert_eq!(&second[123..125], &91u16.to_be_bytes());
+        assert_eq!(first[125], 0x00);
+        assert_eq!(second[125], 0x00);
+        assert_eq!([&first[126..], &second[126..]].concat(), data);
+    }
+}
@@ -0,0 +1,12 @@
+use super::{Output, RawColor};
+use crate::errors::PixelsError;
+
+/// Discards every frame, useful for exercising the controller without any way to show the strip
+#[derive(Debug)]
+pub struct Null;
+
+impl Output for Null {
+    fn render(&mut self, _: &[RawColor]) -> Result<(), PixelsError> {
+        Ok(())
+    }
+}
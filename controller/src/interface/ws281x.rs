@@ -0,0 +1,61 @@
+use super::{Output, RawColor};
+use crate::{
+    config::{Config, StripType},
+    errors::PixelsError,
+};
+use rs_ws281x::{self, ChannelBuilder, Controller, ControllerBuilder};
+
+// From https://github.com/adafruit/Adafruit_Blinka/blob/7.0.1/src/adafruit_blinka/microcontroller/bcm283x/neopixel.py#L9-L13
+const LED_CHANNEL: usize = 0;
+const LED_FREQUENCY: u32 = 800_000;
+const LED_DMA_CHANNEL: i32 = 10;
+const LED_BRIGHTNESS: u8 = 255;
+const LED_INVERT: bool = false;
+
+// Currently we don't support changing the pin. This corresponds to GPIO 18 (pin 12) on the Raspberry Pi
+const LED_PIN: i32 = 18;
+
+/// A strip connected to the GPIO pins of a Raspberry Pi, driven by rpi_ws281x
+pub struct Ws281x {
+    controller: Controller,
+}
+
+impl Ws281x {
+    /// Connect to the strip described by the configuration
+    pub fn new(config: &Config) -> Result<Self, PixelsError> {
+        let controller = ControllerBuilder::new()
+            .freq(LED_FREQUENCY)
+            .dma(LED_DMA_CHANNEL)
+            .channel(
+                LED_CHANNEL,
+                ChannelBuilder::new()
+                    .pin(LED_PIN)
+                    .count(config.leds as i32)
+                    .strip_type(hardware_strip_type(config.strip_type))
+                    .brightness(LED_BRIGHTNESS)
+                    .invert(LED_INVERT)
+                    .build(),
+            )
+            .build()?;
+
+        Ok(Ws281x { controller })
+    }
+}
+
+impl Output for Ws281x {
+    fn render(&mut self, leds: &[RawColor]) -> Result<(), PixelsError> {
+        self.controller.leds_mut(LED_CHANNEL).copy_from_slice(leds);
+        self.controller.render()?;
+        Ok(())
+    }
+}
+
+/// Get the strip type to configure the hardware with. Channels are reordered before being written
+/// to the controller, so the hardware only needs to know how many channels each LED has.
+fn hardware_strip_type(strip_type: StripType) -> rs_ws281x::StripType {
+    if strip_type.has_white() {
+        rs_ws281x::StripType::Sk6812Rgbw
+    } else {
+        rs_ws281x::StripType::Ws2811Rgb
+    }
+}
@@ -1,4 +1,22 @@
-use crate::{animations::SharedAnimator, pixels::Pixels};
+use crate::{
+    animations::{
+        self, Metadata, ParameterColor, ParameterError, ParameterKind, PlayError, SharedAnimator,
+        Value, Values,
+    },
+    clips::Clips,
+    config::CaptureFormat,
+    errors::{CaptureError, ClipError, InvalidCron, UnknownPreset, UnknownSegment},
+    notifier::{Notification, Notifier, Pattern},
+    pixels::{self, Blend, LayerId, Pixels},
+    power,
+    presets::Presets,
+    schedule::{self, Scheduler},
+    sleep::SleepTimer,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tonic::{Request, Response, Status};
 use tracing::{error, info, instrument};
 
@@ -8,8 +26,15 @@ mod pb {
 
 use pb::{
     controller_server::{Controller, ControllerServer},
-    AnimationStatus, BrightnessArgs, Color, Empty, RegisterAnimationArgs, SetAllArgs, SetArgs,
-    StartAnimationArgs, UnregisterAnimationArgs,
+    AnimationInfo, AnimationList, AnimationParameter, AnimationStatus, ApplyPresetArgs,
+    BrightnessArgs, BypassCalibrationArgs, CaptureArgs, CaptureResult, ClearLayerArgs, Clip,
+    ClipInfo, ClipList, Color, ConfigureAnimationArgs, ConfigureLayerArgs, CurrentPlaylistArgs,
+    DeleteClipArgs, DeletePlaylistArgs, DeletePresetArgs, DownloadClipArgs, Empty, FillSegmentArgs,
+    GetAnimationArgs, NotifyArgs, ParameterValue, PlayClipArgs, PlayPlaylistArgs, Playlist,
+    PlaylistEntry, PlaylistList, PlaylistStatus, PowerStatus, PowerSwitchArgs, Preset, PresetList,
+    RecordClipArgs, RegisterAnimationArgs, RemoveOverlayArgs, RemoveScheduleArgs, RenderStatistics,
+    SavePresetArgs, Schedule, ScheduleAction, ScheduleList, SetAllArgs, SetArgs, SleepTimerArgs,
+    SleepTimerStatus, StartAnimationArgs, StopAnimationArgs, SunTrigger, UnregisterAnimationArgs,
 };
 
 /// Ensure the provided value is in the range and cast to the specified type. If only a type is
@@ -30,14 +55,63 @@ macro_rules! in_range {
     };
 }
 
+/// Convert a color from a request into a color for the strip, ensuring each channel is in range
+macro_rules! color {
+    ($color:expr) => {{
+        let r = in_range!($color.r, u8);
+        let g = in_range!($color.g, u8);
+        let b = in_range!($color.b, u8);
+        match $color.w {
+            Some(w) => pixels::Color::rgbw(r, g, b, in_range!(w, u8)),
+            None => pixels::Color::rgb(r, g, b),
+        }
+    }};
+}
+
+/// The longest a single repetition of a notification can last in milliseconds
+const MAX_NOTIFICATION_PERIOD: u32 = 10_000;
+
+/// The most times a notification can be repeated
+const MAX_NOTIFICATION_REPEAT: u32 = 100;
+
+/// The longest a preset can take to crossfade in milliseconds
+const MAX_PRESET_TRANSITION: u32 = 60_000;
+
+/// The longest a playlist can take to crossfade between entries in milliseconds
+const MAX_PLAYLIST_TRANSITION: u32 = 60_000;
+
+/// The longest frames can be captured for in seconds
+const MAX_CAPTURE_DURATION: u32 = 60;
+
+/// The longest a clip can be recorded for in seconds
+const MAX_CLIP_DURATION: u32 = 60;
+
+/// The fastest a clip can be played as a percentage of its original speed
+const MAX_CLIP_SPEED: u32 = 1000;
+
+/// The furthest a schedule can run from sunrise or sunset in minutes
+const MAX_SUN_OFFSET: i32 = 12 * 60;
+
 pub type Service = ControllerServer<ControllerService>;
 
 /// Create an instance of the service implementation to run
-pub fn service(animator: SharedAnimator, length: u16, pixels: Pixels) -> Service {
+pub fn service(
+    animator: SharedAnimator,
+    clips: Clips,
+    notifier: Notifier,
+    presets: Presets,
+    scheduler: Scheduler,
+    sleep_timer: SleepTimer,
+    pixels: Pixels,
+) -> Service {
     ControllerServer::new(ControllerService {
         animator,
+        clips,
+        notifier,
+        presets,
+        scheduler,
+        sleep_timer,
         pixels,
-        length,
     })
 }
 
@@ -45,8 +119,269 @@ pub fn service(animator: SharedAnimator, length: u16, pixels: Pixels) -> Service
 #[derive(Debug)]
 pub struct ControllerService {
     animator: SharedAnimator,
+    clips: Clips,
+    notifier: Notifier,
+    presets: Presets,
+    scheduler: Scheduler,
+    sleep_timer: SleepTimer,
     pixels: Pixels,
-    length: u16,
+}
+
+impl ControllerService {
+    /// Get the pixels for a segment, or the entire strip if the segment is empty
+    fn pixels(&self, segment: &str) -> Result<Pixels, UnknownSegment> {
+        match segment {
+            "" => Ok(self.pixels.clone()),
+            name => self
+                .pixels
+                .segment(name)
+                .ok_or_else(|| UnknownSegment(name.to_owned())),
+        }
+    }
+}
+
+impl From<UnknownSegment> for Status {
+    fn from(e: UnknownSegment) -> Self {
+        Status::not_found(e.to_string())
+    }
+}
+
+impl From<ParameterError> for Status {
+    fn from(e: ParameterError) -> Self {
+        match e {
+            ParameterError::UnknownSegment(_) => Status::not_found(e.to_string()),
+            ParameterError::NotRunning => Status::failed_precondition(e.to_string()),
+            ParameterError::Unknown(_) | ParameterError::Invalid(..) => {
+                Status::invalid_argument(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<PlayError> for Status {
+    fn from(e: PlayError) -> Self {
+        Status::not_found(e.to_string())
+    }
+}
+
+impl From<UnknownPreset> for Status {
+    fn from(e: UnknownPreset) -> Self {
+        Status::not_found(e.to_string())
+    }
+}
+
+impl From<CaptureError> for Status {
+    fn from(e: CaptureError) -> Self {
+        match e {
+            CaptureError::NotSupported => Status::unimplemented(e.to_string()),
+            CaptureError::InProgress => Status::already_exists(e.to_string()),
+            CaptureError::Empty => Status::failed_precondition(e.to_string()),
+            CaptureError::Unavailable => Status::unavailable(e.to_string()),
+            CaptureError::IO(_) | CaptureError::Encoding(_) => Status::internal(e.to_string()),
+        }
+    }
+}
+
+impl From<ClipError> for Status {
+    fn from(e: ClipError) -> Self {
+        match e {
+            ClipError::Unavailable => Status::unavailable(e.to_string()),
+            ClipError::InProgress => Status::already_exists(e.to_string()),
+            ClipError::Empty | ClipError::IO(_) => Status::internal(e.to_string()),
+            ClipError::UnknownClip(_) => Status::not_found(e.to_string()),
+            ClipError::InvalidName(_) | ClipError::Invalid(_) => {
+                Status::invalid_argument(e.to_string())
+            }
+        }
+    }
+}
+
+/// Convert the name of a blend mode from a request
+fn blend(name: &str) -> Option<Blend> {
+    match name {
+        "" | "normal" => Some(Blend::Normal),
+        "add" => Some(Blend::Add),
+        "multiply" => Some(Blend::Multiply),
+        "max" => Some(Blend::Max),
+        _ => None,
+    }
+}
+
+/// Convert the name of a notification pattern from a request
+fn pattern(name: &str) -> Option<Pattern> {
+    match name {
+        "" | "blink" => Some(Pattern::Blink),
+        "pulse" => Some(Pattern::Pulse),
+        "chase" => Some(Pattern::Chase),
+        _ => None,
+    }
+}
+
+/// Convert the name of a capture format from a request
+fn capture_format(name: &str) -> Option<CaptureFormat> {
+    match name {
+        "" | "png" => Some(CaptureFormat::Png),
+        "gif" => Some(CaptureFormat::Gif),
+        _ => None,
+    }
+}
+
+/// Convert the name of a sun event from a request
+fn sun_event(name: &str) -> Option<schedule::Event> {
+    match name {
+        "sunrise" => Some(schedule::Event::Sunrise),
+        "sunset" => Some(schedule::Event::Sunset),
+        _ => None,
+    }
+}
+
+/// Convert an optional segment name from a request
+fn segment(name: &str) -> Option<&str> {
+    match name {
+        "" => None,
+        name => Some(name),
+    }
+}
+
+/// Convert a clip into its representation for a response
+fn clip_info(name: String, clip: &pixels::Clip) -> ClipInfo {
+    ClipInfo {
+        name,
+        leds: clip.leds() as u32,
+        frames: clip.frames() as u32,
+        length: clip.length().as_millis() as u64,
+    }
+}
+
+/// Convert the parameters of an animation from a request
+fn parameter_values(parameters: HashMap<String, ParameterValue>) -> Result<Values, ParameterError> {
+    use pb::parameter_value::Value as Kind;
+
+    let mut values = Values::new();
+    for (name, parameter) in parameters {
+        let value = match parameter.value {
+            Some(Kind::Number(n)) => Value::Number(n),
+            Some(Kind::Color(c)) => {
+                let channels = [Some(c.r), Some(c.g), Some(c.b), c.w];
+                if channels.iter().flatten().any(|&c| c > u8::MAX as u32) {
+                    let reason = "channels must be between 0 and 255";
+                    return Err(ParameterError::Invalid(name, reason));
+                }
+
+                Value::Color(ParameterColor {
+                    r: c.r as u8,
+                    g: c.g as u8,
+                    b: c.b as u8,
+                    w: c.w.map(|w| w as u8),
+                })
+            }
+            Some(Kind::Boolean(b)) => Value::Boolean(b),
+            Some(Kind::String(s)) => Value::String(s),
+            None => return Err(ParameterError::Invalid(name, "missing value")),
+        };
+        values.insert(name, value);
+    }
+
+    Ok(values)
+}
+
+/// Convert the metadata of an animation into its representation for a response
+fn animation_info(id: String, metadata: Metadata) -> AnimationInfo {
+    use pb::{
+        animation_parameter::Kind, BooleanParameter, ColorParameter, NumberParameter,
+        StringParameter,
+    };
+
+    let color = |c: ParameterColor| Color {
+        r: c.r as u32,
+        g: c.g as u32,
+        b: c.b as u32,
+        w: c.w.map(|w| w as u32),
+    };
+    let parameters = metadata
+        .parameters
+        .into_iter()
+        .map(|(name, parameter)| AnimationParameter {
+            name,
+            description: parameter.description.unwrap_or_default(),
+            kind: Some(match parameter.kind {
+                ParameterKind::Number { default, min, max } => {
+                    Kind::Number(NumberParameter { default, min, max })
+                }
+                ParameterKind::Color { default } => Kind::Color(ColorParameter {
+                    default: Some(color(default)),
+                }),
+                ParameterKind::Boolean { default } => Kind::Boolean(BooleanParameter { default }),
+                ParameterKind::String { default, options } => {
+                    Kind::String(StringParameter { default, options })
+                }
+            }),
+        })
+        .collect();
+
+    AnimationInfo {
+        id,
+        name: metadata.name.unwrap_or_default(),
+        author: metadata.author.unwrap_or_default(),
+        description: metadata.description.unwrap_or_default(),
+        version: metadata.version.unwrap_or_default(),
+        leds: metadata.leds.unwrap_or_default() as u32,
+        parameters,
+    }
+}
+
+/// Convert a schedule into its representation for a response
+fn schedule(entry: schedule::Entry) -> Schedule {
+    use pb::{schedule::Trigger, schedule_action::Action};
+
+    let (action, segment) = match entry.schedule.action {
+        schedule::Action::Fill { color, segment } => {
+            let (r, g, b, w) = color.to_8bit();
+            let color = Color {
+                r: r as u32,
+                g: g as u32,
+                b: b as u32,
+                w: w.map(u32::from),
+            };
+            (Action::Fill(color), segment)
+        }
+        schedule::Action::Brightness { value, segment } => {
+            (Action::Brightness(value as u32), segment)
+        }
+        schedule::Action::StartAnimation { id, segment } => (Action::StartAnimation(id), segment),
+        schedule::Action::StopAnimation { segment } => (Action::StopAnimation(Empty {}), segment),
+        schedule::Action::ApplyPreset { name, transition } => {
+            let args = ApplyPresetArgs {
+                name,
+                transition: transition as u32,
+            };
+            (Action::ApplyPreset(args), None)
+        }
+    };
+
+    let trigger = match entry.schedule.trigger {
+        schedule::Trigger::Cron(cron) => Trigger::Cron(cron.to_string()),
+        schedule::Trigger::Sun(sun) => Trigger::Sun(SunTrigger {
+            event: sun.event.to_string(),
+            offset: sun.offset,
+        }),
+    };
+
+    Schedule {
+        name: entry.name,
+        trigger: Some(trigger),
+        enabled: entry.schedule.enabled,
+        action: Some(ScheduleAction {
+            action: Some(action),
+            segment: segment.unwrap_or_default(),
+        }),
+        next_run: entry
+            .next
+            .map(|next| match next.duration_since(UNIX_EPOCH) {
+                Ok(since) => since.as_secs() as i64,
+                Err(e) => -(e.duration().as_secs() as i64),
+            }),
+    }
 }
 
 #[tonic::async_trait]
@@ -54,47 +389,48 @@ impl Controller for ControllerService {
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
     async fn set(&self, request: Request<SetArgs>) -> Result<Response<Empty>, Status> {
         let args = request.into_inner();
+        let pixels = self
+            .pixels(&args.segment)?
+            .layer(LayerId::parse(&args.layer));
         let color = args
             .color
             .ok_or_else(|| Status::invalid_argument("missing argument 'color'"))?;
-        let r = in_range!(color.r, u8);
-        let g = in_range!(color.g, u8);
-        let b = in_range!(color.b, u8);
+        let resolved = color!(color);
 
         for index in &args.indexes {
-            let index = in_range!(*index, self.length, u16);
-            self.pixels.set(index, r, g, b);
+            let index = in_range!(*index, pixels.length() - 1, u16);
+            pixels.set(index, resolved);
         }
 
-        self.pixels.show();
+        pixels.show();
 
-        info!(indexes = ?args.indexes, ?color, "set pixel(s) to color");
+        info!(indexes = ?args.indexes, ?color, segment = %args.segment, layer = %args.layer, "set pixel(s) to color");
 
         Ok(Response::new(Empty {}))
     }
 
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
     async fn set_all(&self, request: Request<SetAllArgs>) -> Result<Response<Empty>, Status> {
-        let colors = request.into_inner().colors;
-        if colors.len() != self.length as usize {
+        let SetAllArgs {
+            colors,
+            segment,
+            layer,
+        } = request.into_inner();
+        let pixels = self.pixels(&segment)?.layer(LayerId::parse(&layer));
+        if colors.len() != pixels.length() as usize {
             return Err(Status::invalid_argument(format!(
                 "colors must have {} elements",
-                self.length
+                pixels.length()
             )));
         }
 
         for (i, color) in colors.iter().enumerate() {
-            self.pixels.set(
-                i as u16,
-                in_range!(color.r, u8),
-                in_range!(color.g, u8),
-                in_range!(color.b, u8),
-            );
+            pixels.set(i as u16, color!(color));
         }
 
-        self.pixels.show();
+        pixels.show();
 
-        info!("set colors of all pixels");
+        info!(%segment, %layer, "set colors of all pixels");
 
         Ok(Response::new(Empty {}))
     }
@@ -103,11 +439,7 @@ impl Controller for ControllerService {
     async fn fill(&self, request: Request<Color>) -> Result<Response<Empty>, Status> {
         let args = request.into_inner();
 
-        self.pixels.fill(
-            in_range!(args.r, u8),
-            in_range!(args.g, u8),
-            in_range!(args.b, u8),
-        );
+        self.pixels.fill(color!(args));
         self.pixels.show();
 
         info!(color = ?args, "filled pixels");
@@ -115,17 +447,559 @@ impl Controller for ControllerService {
         Ok(Response::new(Empty {}))
     }
 
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn fill_segment(
+        &self,
+        request: Request<FillSegmentArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let args = request.into_inner();
+        let pixels = self
+            .pixels(&args.segment)?
+            .layer(LayerId::parse(&args.layer));
+        let color = args
+            .color
+            .ok_or_else(|| Status::invalid_argument("missing argument 'color'"))?;
+
+        pixels.fill(color!(color));
+        pixels.show();
+
+        info!(?color, segment = %args.segment, layer = %args.layer, "filled segment");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn configure_layer(
+        &self,
+        request: Request<ConfigureLayerArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let args = request.into_inner();
+        let blend = blend(&args.blend).ok_or_else(|| {
+            Status::invalid_argument(format!("unknown blend mode {:?}", args.blend))
+        })?;
+        let pixels = self.pixels.layer(LayerId::parse(&args.layer));
+
+        pixels.configure_layer(in_range!(args.opacity, u8), blend);
+        pixels.show();
+
+        info!(layer = %args.layer, opacity = %args.opacity, ?blend, "configured layer");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn clear_layer(
+        &self,
+        request: Request<ClearLayerArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let ClearLayerArgs { layer, segment } = request.into_inner();
+        let pixels = self.pixels(&segment)?.layer(LayerId::parse(&layer));
+
+        pixels.clear();
+        pixels.show();
+
+        info!(%layer, %segment, "cleared layer");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn remove_overlay(
+        &self,
+        request: Request<RemoveOverlayArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        if !matches!(LayerId::parse(&name), LayerId::Overlay(_)) {
+            return Err(Status::invalid_argument(format!(
+                "layer {name:?} is not an overlay"
+            )));
+        }
+
+        self.pixels.remove_overlay(&name);
+        self.pixels.show();
+
+        info!(%name, "removed overlay");
+
+        Ok(Response::new(Empty {}))
+    }
+
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
     async fn brightness(
         &self,
         request: Request<BrightnessArgs>,
     ) -> Result<Response<Empty>, Status> {
-        let brightness = request.into_inner().brightness;
+        let BrightnessArgs {
+            brightness,
+            segment,
+        } = request.into_inner();
+        let pixels = self.pixels(&segment)?;
 
-        self.pixels.brightness(in_range!(brightness, u8));
+        pixels.brightness(in_range!(brightness, u8));
+        pixels.show();
+
+        info!(%brightness, %segment, "changed brightness");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn bypass_calibration(
+        &self,
+        request: Request<BypassCalibrationArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let bypass = request.into_inner().bypass;
+
+        self.pixels.bypass_calibration(bypass);
         self.pixels.show();
 
-        info!(%brightness, "changed brightness");
+        info!(%bypass, "changed calibration bypass");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn power(&self, request: Request<Empty>) -> Result<Response<PowerStatus>, Status> {
+        let status = self
+            .pixels
+            .power()
+            .await
+            .ok_or_else(|| Status::unavailable("pixel manager is not running"))?;
+
+        Ok(Response::new(PowerStatus {
+            budget: status.budget,
+            estimated: status.estimated,
+            drawn: status.drawn,
+            limiting: status.limiting,
+            limit_events: status.events,
+            on: status.on,
+        }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn power_off(
+        &self,
+        request: Request<PowerSwitchArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let fade = request.into_inner().fade;
+        power::off(
+            &self.pixels,
+            &self.animator,
+            Duration::from_millis(fade as u64),
+        )
+        .await;
+        info!(%fade, "turned strip off");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn power_on(&self, request: Request<PowerSwitchArgs>) -> Result<Response<Empty>, Status> {
+        let fade = request.into_inner().fade;
+        power::on(
+            &self.pixels,
+            &self.animator,
+            Duration::from_millis(fade as u64),
+        )
+        .await;
+        info!(%fade, "turned strip on");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn render_stats(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<RenderStatistics>, Status> {
+        let stats = self
+            .pixels
+            .render_stats()
+            .await
+            .ok_or_else(|| Status::unavailable("pixel manager is not running"))?;
+
+        Ok(Response::new(RenderStatistics {
+            frames: stats.frames,
+            shows: stats.shows,
+            mean_lateness: stats.mean_lateness,
+            jitter: stats.jitter,
+            max_lateness: stats.max_lateness,
+            mean_render_time: stats.mean_render_time,
+            max_render_time: stats.max_render_time,
+        }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn capture(
+        &self,
+        request: Request<CaptureArgs>,
+    ) -> Result<Response<CaptureResult>, Status> {
+        let args = request.into_inner();
+        let duration = match args.duration {
+            0 => return Err(Status::out_of_range("duration must be greater than 0")),
+            duration => in_range!(duration, MAX_CAPTURE_DURATION, u64),
+        };
+        let format = capture_format(&args.format).ok_or_else(|| {
+            Status::invalid_argument(format!("unknown capture format {:?}", args.format))
+        })?;
+
+        let path = self
+            .pixels
+            .capture(Duration::from_secs(duration), format)
+            .await?;
+        info!(path = %path.display(), %duration, "captured frames");
+
+        Ok(Response::new(CaptureResult {
+            path: path.display().to_string(),
+        }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn record_clip(
+        &self,
+        request: Request<RecordClipArgs>,
+    ) -> Result<Response<ClipInfo>, Status> {
+        let RecordClipArgs { name, duration } = request.into_inner();
+        let duration = match duration {
+            0 => return Err(Status::out_of_range("duration must be greater than 0")),
+            duration => in_range!(duration, MAX_CLIP_DURATION, u64),
+        };
+
+        let clip = self.pixels.record(Duration::from_secs(duration)).await?;
+        self.clips.save(&name, &clip).await?;
+        info!(%name, %duration, frames = %clip.frames(), "recorded clip");
+
+        Ok(Response::new(clip_info(name, &clip)))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn download_clip(
+        &self,
+        request: Request<DownloadClipArgs>,
+    ) -> Result<Response<Clip>, Status> {
+        let name = request.into_inner().name;
+        let data = self.clips.read(&name).await?;
+        Ok(Response::new(Clip { name, data }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn upload_clip(&self, request: Request<Clip>) -> Result<Response<ClipInfo>, Status> {
+        let Clip { name, data } = request.into_inner();
+
+        let clip = pixels::Clip::decode(&data)?;
+        self.clips.save(&name, &clip).await?;
+        info!(%name, frames = %clip.frames(), "uploaded clip");
+
+        Ok(Response::new(clip_info(name, &clip)))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn list_clips(&self, request: Request<Empty>) -> Result<Response<ClipList>, Status> {
+        let clips = self
+            .clips
+            .list()
+            .await?
+            .into_iter()
+            .map(|(name, clip)| clip_info(name, &clip))
+            .collect();
+
+        Ok(Response::new(ClipList { clips }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn delete_clip(
+        &self,
+        request: Request<DeleteClipArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        self.clips.delete(&name).await?;
+        info!(%name, "deleted clip");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn play_clip(&self, request: Request<PlayClipArgs>) -> Result<Response<Empty>, Status> {
+        let args = request.into_inner();
+        let speed = match args.speed {
+            0 => 100,
+            speed => in_range!(speed, MAX_CLIP_SPEED, u32),
+        };
+
+        let clip = self.clips.load(&args.name).await?;
+        self.pixels.play(clip, speed as f64 / 100.0, args.repeat);
+        info!(name = %args.name, %speed, repeat = %args.repeat, "playing clip");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn stop_clip(&self, request: Request<Empty>) -> Result<Response<Empty>, Status> {
+        self.pixels.stop_clip();
+        info!("stopped clip");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn set_sleep_timer(
+        &self,
+        request: Request<SleepTimerArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let SleepTimerArgs { duration, fade } = request.into_inner();
+        let fade = in_range!(fade, duration, u64);
+
+        self.sleep_timer
+            .set(
+                Duration::from_secs(duration as u64),
+                Duration::from_secs(fade),
+            )
+            .await;
+
+        info!(%duration, %fade, "set sleep timer");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn cancel_sleep_timer(&self, request: Request<Empty>) -> Result<Response<Empty>, Status> {
+        self.sleep_timer.cancel().await;
+        info!("cancelled sleep timer");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn sleep_timer(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<SleepTimerStatus>, Status> {
+        let status = match self.sleep_timer.current() {
+            Some(timer) => SleepTimerStatus {
+                active: true,
+                remaining: timer.remaining().as_secs(),
+                fade: timer.fade.as_secs(),
+            },
+            None => SleepTimerStatus {
+                active: false,
+                remaining: 0,
+                fade: 0,
+            },
+        };
+
+        Ok(Response::new(status))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn save_preset(
+        &self,
+        request: Request<SavePresetArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        if name.is_empty() {
+            return Err(Status::invalid_argument("missing argument 'name'"));
+        }
+
+        match self.presets.save(&name).await {
+            Ok(()) => {
+                info!(%name, "saved preset");
+                Ok(Response::new(Empty {}))
+            }
+            Err(err) => {
+                error!(%name, %err, "failed to save preset");
+                Err(Status::aborted("failed to save preset"))
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn apply_preset(
+        &self,
+        request: Request<ApplyPresetArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let ApplyPresetArgs { name, transition } = request.into_inner();
+        let transition = in_range!(transition, MAX_PRESET_TRANSITION, u64);
+
+        self.presets
+            .apply(&name, Duration::from_millis(transition))
+            .await?;
+        info!(%name, %transition, "applied preset");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn list_presets(&self, request: Request<Empty>) -> Result<Response<PresetList>, Status> {
+        let presets = self
+            .presets
+            .list()
+            .await
+            .into_iter()
+            .map(|(name, preset)| Preset {
+                name,
+                brightness: preset.brightness as u32,
+                animation: preset.animation,
+            })
+            .collect();
+
+        Ok(Response::new(PresetList { presets }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn delete_preset(
+        &self,
+        request: Request<DeletePresetArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        match self.presets.delete(&name).await {
+            Ok(()) => {
+                info!(%name, "deleted preset");
+                Ok(Response::new(Empty {}))
+            }
+            Err(err) => {
+                error!(%name, %err, "failed to save presets");
+                Err(Status::aborted("failed to save presets"))
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn add_schedule(&self, request: Request<Schedule>) -> Result<Response<Empty>, Status> {
+        use pb::{schedule::Trigger, schedule_action::Action};
+
+        let args = request.into_inner();
+        if args.name.is_empty() {
+            return Err(Status::invalid_argument("missing argument 'name'"));
+        }
+
+        let trigger = match args.trigger {
+            Some(Trigger::Cron(cron)) => schedule::Trigger::Cron(
+                cron.parse()
+                    .map_err(|e: InvalidCron| Status::invalid_argument(e.to_string()))?,
+            ),
+            Some(Trigger::Sun(sun)) => {
+                if !self.scheduler.has_location() {
+                    return Err(Status::failed_precondition(
+                        "the controller's location is not configured",
+                    ));
+                }
+                if !(-MAX_SUN_OFFSET..=MAX_SUN_OFFSET).contains(&sun.offset) {
+                    return Err(Status::out_of_range(format!(
+                        "offset must be between -{MAX_SUN_OFFSET} and {MAX_SUN_OFFSET}"
+                    )));
+                }
+
+                let event = sun_event(&sun.event).ok_or_else(|| {
+                    Status::invalid_argument(format!("unknown sun event {:?}", sun.event))
+                })?;
+                schedule::Trigger::Sun(schedule::Sun {
+                    event,
+                    offset: sun.offset,
+                })
+            }
+            None => return Err(Status::invalid_argument("missing argument 'trigger'")),
+        };
+        let ScheduleAction {
+            action,
+            segment: name,
+        } = args
+            .action
+            .ok_or_else(|| Status::invalid_argument("missing argument 'action'"))?;
+
+        // Ensure the segment exists before saving the schedule
+        self.pixels(&name)?;
+        let segment = segment(&name).map(str::to_owned);
+
+        let action = match action {
+            Some(Action::Fill(color)) => schedule::Action::Fill {
+                color: color!(color),
+                segment,
+            },
+            Some(Action::Brightness(value)) => schedule::Action::Brightness {
+                value: in_range!(value, u8),
+                segment,
+            },
+            Some(Action::StartAnimation(id)) => schedule::Action::StartAnimation { id, segment },
+            Some(Action::StopAnimation(_)) => schedule::Action::StopAnimation { segment },
+            Some(Action::ApplyPreset(args)) => schedule::Action::ApplyPreset {
+                transition: in_range!(args.transition, MAX_PRESET_TRANSITION, u64),
+                name: args.name,
+            },
+            None => return Err(Status::invalid_argument("missing argument 'action'")),
+        };
+
+        let schedule = schedule::Schedule {
+            enabled: args.enabled,
+            trigger,
+            action,
+        };
+        if let Err(err) = self.scheduler.add(&args.name, schedule).await {
+            error!(name = %args.name, %err, "failed to save schedule");
+            return Err(Status::aborted("failed to save schedule"));
+        }
+
+        info!(name = %args.name, "added schedule");
+
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn remove_schedule(
+        &self,
+        request: Request<RemoveScheduleArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        match self.scheduler.remove(&name).await {
+            Ok(()) => {
+                info!(%name, "removed schedule");
+                Ok(Response::new(Empty {}))
+            }
+            Err(err) => {
+                error!(%name, %err, "failed to save schedules");
+                Err(Status::aborted("failed to save schedules"))
+            }
+        }
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn list_schedules(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<ScheduleList>, Status> {
+        let schedules = self.scheduler.list().await;
+        Ok(Response::new(ScheduleList {
+            schedules: schedules.into_iter().map(schedule).collect(),
+        }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn notify(&self, request: Request<NotifyArgs>) -> Result<Response<Empty>, Status> {
+        let args = request.into_inner();
+        let color = args
+            .color
+            .ok_or_else(|| Status::invalid_argument("missing argument 'color'"))?;
+        let pattern = pattern(&args.pattern).ok_or_else(|| {
+            Status::invalid_argument(format!("unknown pattern {:?}", args.pattern))
+        })?;
+        let period = match args.period {
+            0 => 500,
+            period => in_range!(period, MAX_NOTIFICATION_PERIOD, u64),
+        };
+
+        self.notifier
+            .notify(Notification {
+                pattern,
+                color: color!(color),
+                repeat: in_range!(args.repeat.max(1), MAX_NOTIFICATION_REPEAT, u32),
+                period: Duration::from_millis(period),
+                priority: args.priority,
+            })
+            .await;
+
+        info!(?color, ?pattern, repeat = %args.repeat, priority = %args.priority, "queued notification");
 
         Ok(Response::new(Empty {}))
     }
@@ -135,20 +1009,185 @@ impl Controller for ControllerService {
         &self,
         request: Request<StartAnimationArgs>,
     ) -> Result<Response<Empty>, Status> {
-        let id = request.into_inner().id;
-        self.animator.start(&id).await;
-        info!(%id, "started animation");
+        let StartAnimationArgs {
+            id,
+            segment: name,
+            parameters,
+        } = request.into_inner();
+        let values = parameter_values(parameters)?;
+
+        self.animator
+            .start_with(segment(&name), &id, values)
+            .await?;
+        info!(%id, segment = %name, "started animation");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn configure_animation(
+        &self,
+        request: Request<ConfigureAnimationArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let ConfigureAnimationArgs {
+            segment: name,
+            parameters,
+        } = request.into_inner();
+        let values = parameter_values(parameters)?;
+
+        self.animator.configure(segment(&name), values).await?;
+        info!(segment = %name, "changed animation parameters");
+        Ok(Response::new(Empty {}))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn stop_animation(
+        &self,
+        request: Request<StopAnimationArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().segment;
+        self.animator.stop(segment(&name)).await?;
+        info!(segment = %name, "stopped current animation");
         Ok(Response::new(Empty {}))
     }
 
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn save_playlist(&self, request: Request<Playlist>) -> Result<Response<Empty>, Status> {
+        let args = request.into_inner();
+        if args.name.is_empty() {
+            return Err(Status::invalid_argument("missing argument 'name'"));
+        }
+        if args.entries.is_empty() {
+            return Err(Status::invalid_argument(
+                "playlists must have at least one entry",
+            ));
+        }
+        if args.entries.iter().any(|entry| entry.duration == 0) {
+            return Err(Status::invalid_argument(
+                "entry durations must be greater than 0",
+            ));
+        }
+
+        let playlist = animations::Playlist {
+            shuffle: args.shuffle,
+            repeat: args.repeat,
+            transition: in_range!(args.transition, MAX_PLAYLIST_TRANSITION, u64),
+            entries: args
+                .entries
+                .into_iter()
+                .map(|entry| animations::Entry {
+                    id: entry.id,
+                    duration: entry.duration as u64,
+                })
+                .collect(),
+        };
+
+        match self.animator.save_playlist(&args.name, playlist).await {
+            Ok(()) => {
+                info!(name = %args.name, "saved playlist");
+                Ok(Response::new(Empty {}))
+            }
+            Err(err) => {
+                error!(name = %args.name, %err, "failed to save playlist");
+                Err(Status::aborted("failed to save playlist"))
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn delete_playlist(
+        &self,
+        request: Request<DeletePlaylistArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let name = request.into_inner().name;
+        match self.animator.delete_playlist(&name).await {
+            Ok(()) => {
+                info!(%name, "deleted playlist");
+                Ok(Response::new(Empty {}))
+            }
+            Err(err) => {
+                error!(%name, %err, "failed to save playlists");
+                Err(Status::aborted("failed to save playlists"))
+            }
+        }
+    }
+
     #[allow(unused_variables)]
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
-    async fn stop_animation(&self, request: Request<Empty>) -> Result<Response<Empty>, Status> {
-        self.animator.stop().await;
-        info!("stopped current animation");
+    async fn list_playlists(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<PlaylistList>, Status> {
+        let playlists = self
+            .animator
+            .playlists()
+            .await
+            .into_iter()
+            .map(|(name, playlist)| Playlist {
+                name,
+                entries: playlist
+                    .entries
+                    .into_iter()
+                    .map(|entry| PlaylistEntry {
+                        id: entry.id,
+                        duration: entry.duration as u32,
+                    })
+                    .collect(),
+                transition: playlist.transition as u32,
+                shuffle: playlist.shuffle,
+                repeat: playlist.repeat,
+            })
+            .collect();
+
+        Ok(Response::new(PlaylistList { playlists }))
+    }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn play_playlist(
+        &self,
+        request: Request<PlayPlaylistArgs>,
+    ) -> Result<Response<Empty>, Status> {
+        let PlayPlaylistArgs {
+            name,
+            segment: segment_name,
+        } = request.into_inner();
+        self.animator.play(segment(&segment_name), &name).await?;
+        info!(%name, segment = %segment_name, "started playlist");
         Ok(Response::new(Empty {}))
     }
 
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn current_playlist(
+        &self,
+        request: Request<CurrentPlaylistArgs>,
+    ) -> Result<Response<PlaylistStatus>, Status> {
+        let name = request.into_inner().segment;
+        self.pixels(&name)?;
+        let segment = segment(&name).map(str::to_owned);
+
+        let status = match self.animator.positions().borrow().get(&segment) {
+            Some(position) => PlaylistStatus {
+                playing: true,
+                name: position.playlist.clone(),
+                index: position.index as u32,
+                id: position.id.clone(),
+                remaining: position
+                    .ends
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default()
+                    .as_secs(),
+            },
+            None => PlaylistStatus {
+                playing: false,
+                name: String::new(),
+                index: 0,
+                id: String::new(),
+                remaining: 0,
+            },
+        };
+
+        Ok(Response::new(status))
+    }
+
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
     async fn register_animation(
         &self,
@@ -156,16 +1195,21 @@ impl Controller for ControllerService {
     ) -> Result<Response<AnimationStatus>, Status> {
         let RegisterAnimationArgs { id, wasm } = request.into_inner();
 
-        let result = self.animator.register(&id, wasm).await;
-        let success = result.is_ok();
-
-        if let Err(err) = result {
-            error!(%id, %err, "failed to register animation");
-        } else {
-            info!(%id, "registered animation");
-        }
+        let animation = match self.animator.register(&id, wasm).await {
+            Ok(metadata) => {
+                info!(%id, "registered animation");
+                Some(animation_info(id, metadata))
+            }
+            Err(err) => {
+                error!(%id, %err, "failed to register animation");
+                None
+            }
+        };
 
-        Ok(Response::new(AnimationStatus { success }))
+        Ok(Response::new(AnimationStatus {
+            success: animation.is_some(),
+            animation,
+        }))
     }
 
     #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
@@ -185,4 +1229,42 @@ impl Controller for ControllerService {
             }
         }
     }
+
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn get_animation(
+        &self,
+        request: Request<GetAnimationArgs>,
+    ) -> Result<Response<AnimationInfo>, Status> {
+        let id = request.into_inner().id;
+        match self.animator.animation(&id).await {
+            Ok(Some(metadata)) => Ok(Response::new(animation_info(id, metadata))),
+            Ok(None) => Err(Status::not_found(format!("unknown animation {:?}", id))),
+            Err(err) => {
+                error!(%id, %err, "failed to get animation");
+                Err(Status::internal("failed to get animation"))
+            }
+        }
+    }
+
+    #[allow(unused_variables)]
+    #[instrument(skip_all, fields(remote_addr = ?request.remote_addr()))]
+    async fn list_animations(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<AnimationList>, Status> {
+        let animations = match self.animator.animations().await {
+            Ok(animations) => animations,
+            Err(err) => {
+                error!(%err, "failed to list animations");
+                return Err(Status::internal("failed to list animations"));
+            }
+        };
+
+        Ok(Response::new(AnimationList {
+            animations: animations
+                .into_iter()
+                .map(|(id, metadata)| animation_info(id, metadata))
+                .collect(),
+        }))
+    }
 }
@@ -6,15 +6,27 @@ use tracing::{debug, info, info_span};
 use tracing_subscriber::fmt::format::FmtSpan;
 
 mod animations;
+mod clips;
 mod config;
 mod errors;
 mod interface;
 mod lights;
+mod notifier;
 mod pixels;
+mod power;
+mod presets;
+mod schedule;
+mod sleep;
+mod state;
 
 use animations::Animator;
+use clips::Clips;
 use config::Config;
+use notifier::Notifier;
 use pixels::Pixels;
+use presets::Presets;
+use schedule::Scheduler;
+use sleep::SleepTimer;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
@@ -37,14 +49,45 @@ async fn main() -> eyre::Result<()> {
     }
 
     // Connect to the pixels
-    let (pixels, pixels_handle) = Pixels::new(config.leds)
+    let (pixels, pixels_handle) = Pixels::new(&config)
         .await
         .wrap_err("failed to setup LEDs")?;
     info!(count = %config.leds, "connected to LED strip");
 
     // Create and start the animator
-    let (animator, animator_handle) =
-        Animator::new(config.animations_path, config.development, pixels.clone());
+    let (animator, animator_handle) = Animator::new(&config, pixels.clone());
+
+    // Load the saved playlists
+    animator
+        .load_playlists()
+        .await
+        .wrap_err("failed to load playlists")?;
+
+    // Load the metadata of the registered animations
+    animator
+        .load_metadata()
+        .await
+        .wrap_err("failed to load animation metadata")?;
+
+    // Create and start the sleep timer
+    let (sleep_timer, sleep_timer_handle) = SleepTimer::new(pixels.clone(), animator.clone());
+
+    // Load the saved presets
+    let presets = Presets::load(&config, pixels.clone(), animator.clone())
+        .await
+        .wrap_err("failed to load presets")?;
+
+    // Load and start running the schedules
+    let (scheduler, scheduler_handle) =
+        Scheduler::new(&config, pixels.clone(), animator.clone(), presets.clone())
+            .await
+            .wrap_err("failed to load schedules")?;
+
+    // Show whatever the strip should start with
+    let persister_handle = state::startup(&config, &pixels, &animator, &sleep_timer).await;
+
+    // Create and start the notifier
+    let (notifier, notifier_handle) = Notifier::new(pixels.clone(), animator.clone());
 
     // Create the health reporter
     let (mut reporter, health_service) = health_reporter();
@@ -57,7 +100,11 @@ async fn main() -> eyre::Result<()> {
         .add_service(health_service)
         .add_service(lights::service(
             animator.clone(),
-            config.leds,
+            Clips::new(&config),
+            notifier.clone(),
+            presets.clone(),
+            scheduler.clone(),
+            sleep_timer.clone(),
             pixels.clone(),
         ))
         .serve_with_shutdown(config.address, async { signal::ctrl_c().await.unwrap() })
@@ -66,6 +113,18 @@ async fn main() -> eyre::Result<()> {
     info!("signal received, shutting down...");
     reporter.set_not_serving::<lights::Service>().await;
 
+    // Stop the notifier
+    notifier.shutdown().await;
+    notifier_handle.await?;
+
+    // Stop the scheduler
+    scheduler.shutdown();
+    scheduler_handle.await?;
+
+    // Stop the sleep timer
+    sleep_timer.shutdown().await;
+    sleep_timer_handle.await?;
+
     // Stop the animator
     animator.shutdown().await;
     animator_handle.await?;
@@ -74,6 +133,11 @@ async fn main() -> eyre::Result<()> {
     pixels.shutdown().await;
     pixels_handle.await?;
 
+    // Wait for the final state to be saved
+    if let Some(handle) = persister_handle {
+        handle.await?;
+    }
+
     info!("shutdown successful. good bye!");
     Ok(())
 }
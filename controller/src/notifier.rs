@@ -0,0 +1,228 @@
+use crate::{
+    animations::{Pause, SharedAnimator},
+    pixels::{Blend, Color, LayerId, Pixels},
+};
+use std::{cmp::Ordering, collections::BinaryHeap, f64::consts::PI, time::Duration};
+use tokio::{
+    sync::mpsc::{self, error::TryRecvError, Receiver, Sender},
+    task::{self, JoinHandle},
+    time,
+};
+use tracing::{error, info, info_span, instrument, Instrument};
+
+/// The overlay notifications are drawn on
+const LAYER: &str = "notification";
+
+/// How often patterns which change smoothly are redrawn
+const FRAME: Duration = Duration::from_millis(20);
+
+/// The shape of a notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Turn the entire strip on and off
+    Blink,
+    /// Fade the entire strip in and out
+    Pulse,
+    /// Sweep a band of color from one end of the strip to the other
+    Chase,
+}
+
+/// A short pattern played over the current contents of the strip
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub pattern: Pattern,
+    pub color: Color,
+    /// How many times the pattern is played
+    pub repeat: u32,
+    /// How long a single repetition of the pattern lasts
+    pub period: Duration,
+    /// Notifications with a higher priority are played first
+    pub priority: u32,
+}
+
+/// The action for the notifier to perform
+#[derive(Debug)]
+enum Action {
+    /// Queue a notification to be played
+    Notify(Notification),
+    /// Shutdown the notifier
+    Shutdown,
+}
+
+/// Plays notifications on an overlay above everything else on the strip. Animations are paused
+/// while notifications play and resumed afterwards, so the strip returns to exactly what was
+/// there before.
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    tx: Sender<Action>,
+}
+
+impl Notifier {
+    /// Create and start a new notifier
+    pub fn new(pixels: Pixels, animator: SharedAnimator) -> (Notifier, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(5);
+        let pixels = pixels.layer(LayerId::Overlay(LAYER.into()));
+        let handle = task::spawn(player(pixels, animator, rx).instrument(info_span!("notifier")));
+
+        (Notifier { tx }, handle)
+    }
+
+    /// Queue a notification to be played once all the notifications with the same or a higher
+    /// priority have finished
+    #[instrument(skip(self))]
+    pub async fn notify(&self, notification: Notification) {
+        if let Err(err) = self.tx.send(Action::Notify(notification)).await {
+            error!(%err, "failed to queue notification");
+        }
+    }
+
+    /// Shutdown the notifier, dropping any queued notifications
+    #[instrument(skip(self))]
+    pub async fn shutdown(&self) {
+        if let Err(err) = self.tx.send(Action::Shutdown).await {
+            error!(%err, "failed to shutdown notifier");
+        }
+    }
+}
+
+/// A notification waiting to be played. Notifications with the same priority are played in the
+/// order they were received.
+#[derive(Debug)]
+struct Queued {
+    sequence: u64,
+    notification: Notification,
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.notification
+            .priority
+            .cmp(&other.notification.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+/// Waits for notifications and plays them in order of priority
+async fn player(pixels: Pixels, animator: SharedAnimator, mut actions: Receiver<Action>) {
+    info!("notifier started");
+    let mut queue = BinaryHeap::new();
+    let mut sequence = 0;
+
+    'outer: loop {
+        // Wait for something to play, then pick up everything else that arrived in the meantime
+        // so the highest priority notification is played next
+        if queue.is_empty() {
+            match actions.recv().await {
+                Some(Action::Notify(notification)) => queue.push(Queued {
+                    sequence,
+                    notification,
+                }),
+                Some(Action::Shutdown) | None => break,
+            }
+            sequence += 1;
+
+            animator.pause(Pause::Notification).await;
+        }
+
+        loop {
+            match actions.try_recv() {
+                Ok(Action::Notify(notification)) => queue.push(Queued {
+                    sequence,
+                    notification,
+                }),
+                Err(TryRecvError::Empty) => break,
+                Ok(Action::Shutdown) | Err(TryRecvError::Disconnected) => break 'outer,
+            }
+            sequence += 1;
+        }
+
+        let Queued { notification, .. } = queue.pop().unwrap();
+        play(&pixels, &notification).await;
+
+        // Restore the strip once there is nothing left to play
+        if queue.is_empty() {
+            pixels.remove_overlay(LAYER);
+            pixels.show();
+            animator.resume(Pause::Notification).await;
+        }
+    }
+
+    pixels.remove_overlay(LAYER);
+    pixels.show();
+
+    info!("shutdown successfully")
+}
+
+/// Play a single notification on the overlay
+#[instrument(skip(pixels))]
+async fn play(pixels: &Pixels, notification: &Notification) {
+    let Notification {
+        pattern,
+        color,
+        repeat,
+        period,
+        ..
+    } = *notification;
+    pixels.configure_layer(u8::MAX, Blend::Normal);
+
+    for _ in 0..repeat {
+        match pattern {
+            Pattern::Blink => {
+                for color in [color, Color::default()] {
+                    pixels.fill(color);
+                    pixels.show();
+                    time::sleep(period / 2).await;
+                }
+            }
+            Pattern::Pulse => {
+                pixels.fill(color);
+                for progress in frames(period) {
+                    let opacity = (progress * PI).sin() * u8::MAX as f64;
+                    pixels.configure_layer(opacity as u8, Blend::Normal);
+                    pixels.show();
+                    time::sleep(FRAME).await;
+                }
+            }
+            Pattern::Chase => {
+                let length = pixels.length() as f64;
+                let width = (length / 10.0).ceil().max(1.0);
+                for progress in frames(period) {
+                    let head = progress * (length + width);
+                    let start = (head - width).max(0.0) as u16;
+                    let end = head.min(length) as u16;
+
+                    pixels.clear();
+                    for index in start..end {
+                        pixels.set(index, color);
+                    }
+                    pixels.show();
+                    time::sleep(FRAME).await;
+                }
+            }
+        }
+    }
+
+    pixels.clear();
+    pixels.configure_layer(u8::MAX, Blend::Normal);
+    pixels.show();
+}
+
+/// The progress through a period at each frame, from 0 up to 1
+fn frames(period: Duration) -> impl Iterator<Item = f64> {
+    let count = (period.as_secs_f64() / FRAME.as_secs_f64()).ceil().max(1.0) as u32;
+    (0..=count).map(move |frame| frame as f64 / count as f64)
+}
@@ -0,0 +1,79 @@
+use super::color::Rgbw;
+use crate::config;
+
+/// Corrects colors for how the LEDs are perceived before they are sent to the strip. Every
+/// correction is combined into a lookup table for each channel so that applying them is cheap.
+#[derive(Debug)]
+pub(crate) struct Calibration {
+    tables: [Vec<u16>; 4],
+    bypass: bool,
+}
+
+impl Calibration {
+    /// Build the lookup tables for the configured corrections
+    pub fn new(config: &config::Calibration) -> Self {
+        let [r, g, b] = config
+            .temperature
+            .map(temperature_scale)
+            .unwrap_or([1.0; 3]);
+        let balance = &config.white_balance;
+        let scales = [balance.r * r, balance.g * g, balance.b * b, balance.w];
+
+        let tables = scales.map(|scale| {
+            (0..=u16::MAX)
+                .map(|value| {
+                    let linear = (value as f32 / u16::MAX as f32).powf(config.gamma);
+                    (linear * scale * u16::MAX as f32)
+                        .round()
+                        .clamp(0.0, u16::MAX as f32) as u16
+                })
+                .collect()
+        });
+
+        Calibration {
+            tables,
+            bypass: false,
+        }
+    }
+
+    /// Set whether colors should pass through unchanged
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    /// Correct a color
+    pub fn apply(&self, color: Rgbw) -> Rgbw {
+        if self.bypass {
+            return color;
+        }
+
+        let mut corrected = color;
+        for (channel, table) in corrected.iter_mut().zip(&self.tables) {
+            *channel = table[*channel as usize];
+        }
+        corrected
+    }
+}
+
+/// Approximate how much each of the red, green, and blue channels should be scaled by to shift
+/// white towards the given color temperature. Based on Tanner Helland's approximation of the
+/// blackbody curve.
+fn temperature_scale(kelvin: u16) -> [f32; 3] {
+    let temperature = kelvin as f32 / 100.0;
+
+    let (r, g, b) = if temperature <= 66.0 {
+        let g = 99.470_8 * temperature.ln() - 161.119_57;
+        let b = if temperature <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (temperature - 10.0).ln() - 305.044_8
+        };
+        (255.0, g, b)
+    } else {
+        let r = 329.698_73 * (temperature - 60.0).powf(-0.133_204_76);
+        let g = 288.122_16 * (temperature - 60.0).powf(-0.075_514_85);
+        (r, g, 255.0)
+    };
+
+    [r, g, b].map(|c: f32| (c / 255.0).clamp(0.0, 1.0))
+}
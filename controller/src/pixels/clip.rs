@@ -0,0 +1,337 @@
+use super::color::{Rgbw, Rgbw8};
+use crate::errors::ClipError;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Identifies clip files
+const MAGIC: &[u8; 4] = b"LCLP";
+
+/// The version of the clip format
+const VERSION: u8 = 1;
+
+/// The size of the header in bytes: the magic number, version, number of LEDs, and length
+const HEADER_SIZE: usize = 11;
+
+/// A recording of the frames written to the strip. Each frame is stored exactly as it was sent to
+/// the LEDs, as red, green, blue, and white, along with when it was shown.
+///
+/// The binary format starts with a header of the magic number `LCLP`, a version byte, the number
+/// of LEDs as a little-endian `u16`, and the length of the clip in milliseconds as a
+/// little-endian `u32`. Each frame follows as its timestamp in milliseconds as a little-endian
+/// `u32`, then 4 bytes for each LED.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clip {
+    leds: u16,
+    length: u32,
+    frames: Vec<(u32, Vec<Rgbw8>)>,
+}
+
+impl Clip {
+    /// The number of LEDs in each frame
+    pub fn leds(&self) -> u16 {
+        self.leds
+    }
+
+    /// The number of frames in the clip
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// How long the clip lasts
+    pub fn length(&self) -> Duration {
+        Duration::from_millis(self.length as u64)
+    }
+
+    /// Serialize the clip to its binary format
+    pub fn encode(&self) -> Vec<u8> {
+        let frame_size = 4 + self.leds as usize * 4;
+        let mut data = Vec::with_capacity(HEADER_SIZE + self.frames.len() * frame_size);
+
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.extend_from_slice(&self.leds.to_le_bytes());
+        data.extend_from_slice(&self.length.to_le_bytes());
+
+        for (timestamp, pixels) in &self.frames {
+            data.extend_from_slice(&timestamp.to_le_bytes());
+            data.extend(pixels.iter().flatten());
+        }
+
+        data
+    }
+
+    /// Parse a clip from its binary format
+    pub fn decode(data: &[u8]) -> Result<Clip, ClipError> {
+        if data.len() < HEADER_SIZE || &data[..4] != MAGIC {
+            return Err(ClipError::Invalid("not a clip"));
+        }
+        if data[4] != VERSION {
+            return Err(ClipError::Invalid("unsupported version"));
+        }
+
+        let leds = u16::from_le_bytes([data[5], data[6]]);
+        let length = u32::from_le_bytes([data[7], data[8], data[9], data[10]]);
+        if leds == 0 {
+            return Err(ClipError::Invalid("must contain at least 1 LED"));
+        }
+
+        let body = &data[HEADER_SIZE..];
+        let chunks = body.chunks_exact(4 + leds as usize * 4);
+        if body.is_empty() || !chunks.remainder().is_empty() {
+            return Err(ClipError::Invalid("incomplete frame"));
+        }
+
+        let mut frames = Vec::with_capacity(chunks.len());
+        for frame in chunks {
+            let timestamp = u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]);
+            let pixels = frame[4..]
+                .chunks(4)
+                .map(|c| [c[0], c[1], c[2], c[3]])
+                .collect();
+            frames.push((timestamp, pixels));
+        }
+
+        // Frames are looked up by their timestamps, so they must be in order and start at the
+        // beginning of the clip
+        if frames[0].0 != 0 {
+            return Err(ClipError::Invalid("first frame must start at 0"));
+        }
+        if frames.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err(ClipError::Invalid("frames must be in order"));
+        }
+        if frames[frames.len() - 1].0 > length {
+            return Err(ClipError::Invalid("frames must end before the clip does"));
+        }
+
+        Ok(Clip {
+            leds,
+            length,
+            frames,
+        })
+    }
+}
+
+/// Collects the frames written to the strip into a clip
+#[derive(Debug)]
+pub(crate) struct Recording {
+    start: Instant,
+    leds: u16,
+    max_frames: usize,
+    frames: Vec<(u32, Vec<Rgbw8>)>,
+}
+
+impl Recording {
+    /// Start recording a strip of LEDs, keeping at most a number of frames
+    pub fn new(leds: u16, max_frames: u32) -> Self {
+        Recording {
+            start: Instant::now(),
+            leds,
+            max_frames: max_frames as usize,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Add a frame that was just written to the strip. Frames are dropped once the recording is
+    /// full.
+    pub fn record(&mut self, pixels: &[Rgbw8]) {
+        if self.frames.len() >= self.max_frames {
+            return;
+        }
+
+        // The first frame always starts the clip, even if it took a moment to be rendered
+        let timestamp = match self.frames.is_empty() {
+            true => 0,
+            false => self.elapsed(),
+        };
+        self.frames.push((timestamp, pixels.to_vec()));
+        if self.frames.len() == self.max_frames {
+            warn!(
+                frames = self.max_frames,
+                "recording reached the maximum number of frames"
+            );
+        }
+    }
+
+    /// Stop recording, producing the clip. A full recording ends at its last frame.
+    pub fn finish(self) -> Result<Clip, ClipError> {
+        let length = match self.frames.last() {
+            Some(_) if self.frames.len() < self.max_frames => self.elapsed(),
+            Some((timestamp, _)) => *timestamp,
+            None => return Err(ClipError::Empty),
+        };
+
+        Ok(Clip {
+            leds: self.leds,
+            length,
+            frames: self.frames,
+        })
+    }
+
+    /// Milliseconds since the recording started
+    fn elapsed(&self) -> u32 {
+        self.start.elapsed().as_millis().min(u32::MAX as u128) as u32
+    }
+}
+
+/// Plays a clip back in place of whatever the strip would otherwise show
+#[derive(Debug)]
+pub(crate) struct Playback {
+    clip: Clip,
+    leds: usize,
+    speed: f64,
+    repeat: bool,
+    start: Instant,
+}
+
+impl Playback {
+    /// Start playing a clip on a strip of LEDs. Clips recorded on a different number of LEDs are
+    /// cut off or padded with black.
+    pub fn new(clip: Clip, leds: u16, speed: f64, repeat: bool) -> Self {
+        Playback {
+            clip,
+            leds: leds as usize,
+            speed,
+            repeat,
+            start: Instant::now(),
+        }
+    }
+
+    /// The frame to show at a point in time, or nothing once a clip which doesn't repeat has
+    /// finished
+    pub fn frame(&self, now: Instant) -> Option<Vec<Rgbw>> {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64() * 1000.0 * self.speed;
+        let elapsed = elapsed as u64;
+        let length = self.clip.length as u64;
+
+        let position = if elapsed < length {
+            elapsed
+        } else if self.repeat {
+            elapsed % length.max(1)
+        } else {
+            return None;
+        };
+
+        let index = self
+            .clip
+            .frames
+            .partition_point(|(timestamp, _)| *timestamp as u64 <= position);
+        let (_, pixels) = &self.clip.frames[index.max(1) - 1];
+
+        let mut pixels = pixels
+            .iter()
+            .map(|pixel| pixel.map(|c| c as u16 * 257))
+            .collect::<Vec<_>>();
+        pixels.resize(self.leds, [0; 4]);
+        Some(pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip() -> Clip {
+        Clip {
+            leds: 3,
+            length: 1000,
+            frames: vec![
+                (0, vec![[255, 0, 0, 0], [0, 255, 0, 0], [0, 0, 255, 0]]),
+                (500, vec![[1, 2, 3, 4], [5, 6, 7, 8], [9, 10, 11, 12]]),
+                (1000, vec![[0; 4], [0; 4], [255; 4]]),
+            ],
+        }
+    }
+
+    fn is_invalid(result: Result<Clip, ClipError>) -> bool {
+        matches!(result, Err(ClipError::Invalid(_)))
+    }
+
+    #[test]
+    fn round_trips() {
+        let clip = clip();
+        let data = clip.encode();
+        assert_eq!(data.len(), HEADER_SIZE + 3 * (4 + 3 * 4));
+        assert_eq!(&data[..5], b"LCLP\x01");
+        assert_eq!(Clip::decode(&data).unwrap(), clip);
+    }
+
+    #[test]
+    fn rejects_truncated() {
+        let data = clip().encode();
+        let frame_size = 4 + 3 * 4;
+        for length in 0..data.len() {
+            let result = Clip::decode(&data[..length]);
+            let frames = length.saturating_sub(HEADER_SIZE) / frame_size;
+
+            // Cutting between frames leaves a shorter clip, which can't be told apart
+            if frames > 0 && length == HEADER_SIZE + frames * frame_size {
+                assert_eq!(result.unwrap().frames(), frames);
+            } else {
+                assert!(is_invalid(result), "{}", length);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let mut data = clip().encode();
+        data[0] = b'X';
+        assert!(is_invalid(Clip::decode(&data)));
+
+        let mut data = clip().encode();
+        data[4] = VERSION + 1;
+        assert!(is_invalid(Clip::decode(&data)));
+
+        // No LEDs
+        let mut data = clip().encode();
+        data[5..7].copy_from_slice(&0u16.to_le_bytes());
+        assert!(is_invalid(Clip::decode(&data)));
+    }
+
+    #[test]
+    fn rejects_bad_frames() {
+        let mut unordered = clip();
+        unordered.frames.swap(1, 2);
+        assert!(is_invalid(Clip::decode(&unordered.encode())));
+
+        let mut late_start = clip();
+        late_start.frames[0].0 = 1;
+        assert!(is_invalid(Clip::decode(&late_start.encode())));
+
+        let mut too_long = clip();
+        too_long.length = 999;
+        assert!(is_invalid(Clip::decode(&too_long.encode())));
+    }
+
+    #[test]
+    fn recording_stops_at_max_frames() {
+        let mut recording = Recording::new(2, 3);
+        for i in 0..5 {
+            recording.record(&[[i; 4]; 2]);
+        }
+
+        // The earliest frames are kept and the clip ends with the last of them
+        let clip = recording.finish().unwrap();
+        assert_eq!(clip.frames(), 3);
+        assert_eq!(clip.frames[2].1, [[2; 4]; 2]);
+        assert_eq!(clip.length, clip.frames[2].0);
+    }
+
+    #[test]
+    fn plays_back() {
+        let start = Instant::now();
+        let mut playback = Playback::new(clip(), 4, 2.0, false);
+        playback.start = start;
+
+        let first = playback.frame(start).unwrap();
+        assert_eq!(first.len(), 4);
+        assert_eq!(first[0], [65535, 0, 0, 0]);
+        assert_eq!(first[3], [0; 4]);
+
+        // Played at double speed, so the second frame is reached after 250ms
+        let second = playback.frame(start + Duration::from_millis(250)).unwrap();
+        assert_eq!(second[0], [257, 514, 771, 1028]);
+
+        assert_eq!(playback.frame(start + Duration::from_millis(500)), None);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{config::ColorOrder, interface::RawColor};
+use serde::{Deserialize, Serialize};
+
+/// A color to display on the strip. Channels are stored with 16 bits of precision so that colors
+/// between two 8-bit values can be shown when dithering is enabled. When the white channel is
+/// omitted, it is derived from the other channels on strips that have a dedicated white LED.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Color {
+    r: u16,
+    g: u16,
+    b: u16,
+    w: Option<u16>,
+}
+
+/// A color with all of its channels resolved for the strip, stored as red, green, blue, white
+pub(crate) type Rgbw = [u16; 4];
+
+/// A color that has been reduced to the 8 bits per channel the LEDs accept
+pub(crate) type Rgbw8 = [u8; 4];
+
+impl Color {
+    /// Create a color from its red, green, and blue channels
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgb16(widen(r), widen(g), widen(b))
+    }
+
+    /// Create a color with an explicit white channel
+    pub fn rgbw(r: u8, g: u8, b: u8, w: u8) -> Self {
+        Self::rgbw16(widen(r), widen(g), widen(b), widen(w))
+    }
+
+    /// Create a color from its high precision red, green, and blue channels
+    pub fn rgb16(r: u16, g: u16, b: u16) -> Self {
+        Color { r, g, b, w: None }
+    }
+
+    /// Create a high precision color with an explicit white channel
+    pub fn rgbw16(r: u16, g: u16, b: u16, w: u16) -> Self {
+        Color {
+            r,
+            g,
+            b,
+            w: Some(w),
+        }
+    }
+
+    /// Reduce the color to 8 bits per channel, returning the red, green, blue, and white channels
+    pub fn to_8bit(self) -> (u8, u8, u8, Option<u8>) {
+        let Color { r, g, b, w } = self;
+        (narrow(r), narrow(g), narrow(b), w.map(narrow))
+    }
+
+    /// Resolve the channels that will be written to the strip. RGBW strips extract the common
+    /// white component of RGB-only colors into the white channel, while RGB strips mix any
+    /// explicit white back into the other channels.
+    pub(crate) fn resolve(self, has_white: bool) -> Rgbw {
+        let Color { r, g, b, w } = self;
+        match (w, has_white) {
+            (Some(w), true) => [r, g, b, w],
+            (None, true) => {
+                let w = r.min(g).min(b);
+                [r - w, g - w, b - w, w]
+            }
+            (Some(w), false) => [
+                r.saturating_add(w),
+                g.saturating_add(w),
+                b.saturating_add(w),
+                0,
+            ],
+            (None, false) => [r, g, b, 0],
+        }
+    }
+}
+
+/// Convert an 8-bit channel to 16 bits such that 0 and 255 map to the ends of the range
+fn widen(value: u8) -> u16 {
+    value as u16 * 257
+}
+
+/// Convert a 16-bit channel to the nearest 8-bit value
+fn narrow(value: u16) -> u8 {
+    ((value as u32 + 128) / 257) as u8
+}
+
+/// Arrange the channels of a color in the order the LEDs expect. The controller is configured to
+/// send the bytes of each raw color in the order 2, 1, 0, 3, so this is the only place where the
+/// channels get reordered.
+pub(crate) fn pack(color: Rgbw8, order: ColorOrder) -> RawColor {
+    let [r, g, b, w] = color;
+    let [first, second, third] = match order {
+        ColorOrder::Rgb => [r, g, b],
+        ColorOrder::Rbg => [r, b, g],
+        ColorOrder::Grb => [g, r, b],
+        ColorOrder::Gbr => [g, b, r],
+        ColorOrder::Brg => [b, r, g],
+        ColorOrder::Bgr => [b, g, r],
+    };
+    [third, second, first, w]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        interface::{self, Mock},
+    };
+
+    const STRIP_TYPES: [&str; 4] = ["ws2811", "ws2812", "sk6812", "sk6812w"];
+    const ORDERS: [(ColorOrder, &str); 6] = [
+        (ColorOrder::Rgb, "rgb"),
+        (ColorOrder::Rbg, "rbg"),
+        (ColorOrder::Grb, "grb"),
+        (ColorOrder::Gbr, "gbr"),
+        (ColorOrder::Brg, "brg"),
+        (ColorOrder::Bgr, "bgr"),
+    ];
+
+    /// The red, green, and blue channels of a color in the order named
+    fn ordered(color: Rgbw8, name: &str) -> Vec<u8> {
+        name.chars()
+            .map(|channel| match channel {
+                'r' => color[0],
+                'g' => color[1],
+                _ => color[2],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packs_in_order() {
+        let color = [0x11, 0x22, 0x33, 0x44];
+        for (order, name) in ORDERS {
+            // The bytes are sent in the order 2, 1, 0, 3
+            let raw = pack(color, order);
+            let sent = [raw[2], raw[1], raw[0], raw[3]];
+
+            let mut expected = ordered(color, name);
+            expected.push(color[3]);
+            assert_eq!(sent.to_vec(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_mock() {
+        for strip_type in STRIP_TYPES {
+            for (_, name) in ORDERS {
+                let config = Config::test(&format!(
+                    "strip_type = {:?}\ncolor_order = {:?}",
+                    strip_type, name
+                ));
+                let white = config.strip_type.has_white();
+                let color = [0x11, 0x22, 0x33, if white { 0x44 } else { 0 }];
+                let raw = pack(color, config.color_order);
+                assert_eq!(
+                    interface::unpack(raw, config.color_order),
+                    color,
+                    "{} {}",
+                    strip_type,
+                    name
+                );
+
+                // The channels are sent in the configured order, with white always last
+                let mut expected = ordered(color, name);
+                if white {
+                    expected.push(color[3]);
+                }
+                let mock = Mock::new(&config);
+                assert_eq!(mock.wire(&[raw]), expected, "{} {}", strip_type, name);
+            }
+        }
+    }
+}
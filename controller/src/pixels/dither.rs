@@ -0,0 +1,106 @@
+use super::color::{Rgbw, Rgbw8};
+
+/// Reduces high precision frames to the 8 bits per channel that the LEDs accept. Without
+/// dithering, each channel is rounded to the nearest value. With dithering, the rounding error of
+/// each channel is carried over into the next frame so that, when rendered at a high enough
+/// refresh rate, the output averages out to the high precision value.
+#[derive(Debug)]
+pub(crate) struct Quantizer {
+    errors: Option<Vec<[u16; 4]>>,
+}
+
+impl Quantizer {
+    /// Create a quantizer for a strip with the given number of LEDs
+    pub fn new(leds: u16, dithering: bool) -> Self {
+        Quantizer {
+            errors: dithering.then(|| vec![[0; 4]; leds as usize]),
+        }
+    }
+
+    /// Reduce a frame to 8 bits per channel
+    pub fn apply(&mut self, pixels: &[Rgbw]) -> Vec<Rgbw8> {
+        match &mut self.errors {
+            None => pixels.iter().map(|pixel| pixel.map(round)).collect(),
+            Some(errors) => pixels
+                .iter()
+                .zip(errors.iter_mut())
+                .map(|(pixel, error)| {
+                    let mut reduced = [0; 4];
+                    for ((value, error), reduced) in pixel.iter().zip(error).zip(&mut reduced) {
+                        let target = *value as u32 + *error as u32;
+                        let output = (target / 257).min(u8::MAX as u32);
+                        *error = (target - output * 257) as u16;
+                        *reduced = output as u8;
+                    }
+                    reduced
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Round a 16-bit channel to the nearest 8-bit value
+fn round(value: u16) -> u8 {
+    ((value as u32 + 128) / 257) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quantizer;
+    use crate::{
+        config::Config,
+        interface::{Mock, Output},
+        pixels::color,
+    };
+
+    /// The number of frames averaged over
+    const FRAMES: usize = 64;
+
+    #[test]
+    fn averages_to_high_precision_value() {
+        let config = Config::test(r#"strip_type = "ws2812""#);
+        let mut mock = Mock::new(&config);
+        let mut quantizer = Quantizer::new(config.leds, true);
+
+        // Falls between the 8-bit steps 128 and 129
+        let value = 128 * 257 + 100;
+        let target = value as f64 / 257.0;
+        let pixels = vec![[value, value, value, 0]; config.leds as usize];
+
+        let mut sums = vec![0u64; config.leds as usize * 3];
+        for _ in 0..FRAMES {
+            let leds = quantizer
+                .apply(&pixels)
+                .into_iter()
+                .map(|pixel| color::pack(pixel, config.color_order))
+                .collect::<Vec<_>>();
+            mock.render(&leds).unwrap();
+
+            for (sum, byte) in sums.iter_mut().zip(mock.wire(&leds)) {
+                *sum += byte as u64;
+            }
+        }
+
+        for sum in sums {
+            let mean = sum as f64 / FRAMES as f64;
+            assert!(
+                (mean - target).abs() <= 1.0 / FRAMES as f64,
+                "mean {} is too far from {}",
+                mean,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn rounds_without_dithering() {
+        let mut quantizer = Quantizer::new(1, false);
+        let value = 128 * 257 + 100;
+        for _ in 0..FRAMES {
+            assert_eq!(
+                quantizer.apply(&[[value, 0, u16::MAX, 129]]),
+                [[128, 0, 255, 1]]
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use super::color::Rgbw;
+use std::time::{Duration, Instant};
+
+/// Fades the output of the strip between on and off. The frame keeps being updated while the
+/// strip is off, it just isn't shown.
+#[derive(Debug)]
+pub(crate) struct Fade {
+    on: bool,
+    from: f32,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Fade {
+    /// Create a fade for a strip that is turned on
+    pub fn new() -> Self {
+        Fade {
+            on: true,
+            from: 1.0,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Whether the strip is turned on, or is fading towards on
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Start fading towards on or off from the current level
+    pub fn switch(&mut self, on: bool, duration: Duration) {
+        let now = Instant::now();
+        self.from = self.level(now);
+        self.on = on;
+        self.start = now;
+        self.duration = duration;
+    }
+
+    /// Whether the output is still changing
+    pub fn active(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) < self.duration
+    }
+
+    /// How much of the frame is shown at a point in time in the range 0.0-1.0
+    fn level(&self, now: Instant) -> f32 {
+        let target = if self.on { 1.0 } else { 0.0 };
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            target
+        } else {
+            let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+            self.from + (target - self.from) * progress
+        }
+    }
+
+    /// Scale the frame by the current level
+    pub fn apply(&self, pixels: &mut [Rgbw], now: Instant) {
+        let level = self.level(now);
+        if level < 1.0 {
+            for channel in pixels.iter_mut().flatten() {
+                *channel = (*channel as f32 * level) as u16;
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::{
+    calibration::Calibration,
+    color::Rgbw,
+    layer::{Blend, Layer, LayerId},
+    Snapshot,
+};
+use crate::config::Segment;
+use std::{ops::Range, sync::Arc};
+use tracing::{debug, warn};
+
+/// The contents of the strip before it gets written to the controller. Each layer is kept
+/// separately and only combined when the frame is composed, so writes to one layer never clobber
+/// another. Segments are tracked separately so that each one can have its own brightness applied.
+#[derive(Debug)]
+pub(crate) struct Frame {
+    leds: usize,
+    base: Layer,
+    animation: Layer,
+    overlays: Vec<(Arc<str>, Layer)>,
+    brightness: u8,
+    segments: Vec<SegmentState>,
+}
+
+/// The position and brightness of a segment within the frame
+#[derive(Debug)]
+struct SegmentState {
+    range: Range<usize>,
+    brightness: u8,
+}
+
+impl Frame {
+    /// Create an empty frame for a strip with the given segments
+    pub fn new(leds: u16, segments: &[Segment]) -> Self {
+        let leds = leds as usize;
+        let mut base = Layer::transparent(leds);
+        base.pixels.fill(Some([0, 0, 0, 0]));
+
+        Frame {
+            leds,
+            base,
+            animation: Layer::transparent(leds),
+            overlays: Vec::new(),
+            brightness: u8::MAX,
+            segments: segments
+                .iter()
+                .map(|s| SegmentState {
+                    range: s.start as usize..s.end as usize,
+                    brightness: u8::MAX,
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the pixels addressed by a segment, or the entire strip if no segment is given
+    fn range(&self, segment: Option<usize>) -> Range<usize> {
+        match segment {
+            Some(id) => self.segments[id].range.clone(),
+            None => 0..self.leds,
+        }
+    }
+
+    /// Get a layer to modify, overlays are created the first time they are used
+    fn layer(&mut self, id: &LayerId) -> &mut Layer {
+        match id {
+            LayerId::Base => &mut self.base,
+            LayerId::Animation => &mut self.animation,
+            LayerId::Overlay(name) => {
+                let position = match self.overlays.iter().position(|(n, _)| n == name) {
+                    Some(position) => position,
+                    None => {
+                        debug!(%name, "created overlay");
+                        self.overlays
+                            .push((name.clone(), Layer::transparent(self.leds)));
+                        self.overlays.len() - 1
+                    }
+                };
+                &mut self.overlays[position].1
+            }
+        }
+    }
+
+    /// Set the color of a pixel relative to the start of the segment
+    pub fn set(&mut self, layer: &LayerId, segment: Option<usize>, index: u16, color: Rgbw) {
+        let range = self.range(segment);
+        let index = range.start + index as usize;
+        if range.contains(&index) {
+            self.layer(layer).pixels[index] = Some(color);
+        } else {
+            warn!(?segment, %index, "pixel index out of range");
+        }
+    }
+
+    /// Set every pixel in the segment to the same color
+    pub fn fill(&mut self, layer: &LayerId, segment: Option<usize>, color: Rgbw) {
+        let range = self.range(segment);
+        self.layer(layer).pixels[range].fill(Some(color));
+    }
+
+    /// Make every pixel in the segment transparent. The base layer is opaque, so it gets turned
+    /// off instead.
+    pub fn clear(&mut self, layer: &LayerId, segment: Option<usize>) {
+        let range = self.range(segment);
+        let value = match layer {
+            LayerId::Base => Some([0, 0, 0, 0]),
+            _ => None,
+        };
+        self.layer(layer).pixels[range].fill(value);
+    }
+
+    /// Change how a layer is combined with the layers below it
+    pub fn configure(&mut self, layer: &LayerId, opacity: u8, blend: Blend) {
+        let layer = self.layer(layer);
+        layer.opacity = opacity;
+        layer.blend = blend;
+    }
+
+    /// Remove an overlay entirely
+    pub fn remove(&mut self, name: &str) {
+        self.overlays.retain(|(n, _)| n.as_ref() != name);
+    }
+
+    /// Change the brightness of a segment, or the entire strip if no segment is given
+    pub fn brightness(&mut self, segment: Option<usize>, value: u8) {
+        match segment {
+            Some(id) => self.segments[id].brightness = value,
+            None => self.brightness = value,
+        }
+    }
+
+    /// Capture the base layer and brightness so they can be restored later
+    pub fn snapshot(&self, segments: &[Segment], on: bool) -> Snapshot {
+        Snapshot {
+            on,
+            pixels: self
+                .base
+                .pixels
+                .iter()
+                .map(|p| p.unwrap_or_default())
+                .collect(),
+            brightness: self.brightness,
+            segments: segments
+                .iter()
+                .zip(&self.segments)
+                .map(|(segment, state)| (segment.name.clone(), state.brightness))
+                .collect(),
+        }
+    }
+
+    /// Replace the base layer and brightness with a snapshot. Pixels beyond the end of the strip
+    /// and segments which no longer exist are ignored.
+    pub fn restore(&mut self, snapshot: &Snapshot, segments: &[Segment]) {
+        for (pixel, &color) in self.base.pixels.iter_mut().zip(&snapshot.pixels) {
+            *pixel = Some(color);
+        }
+
+        self.brightness = snapshot.brightness;
+        for (segment, state) in segments.iter().zip(&mut self.segments) {
+            if let Some(&brightness) = snapshot.segments.get(&segment.name) {
+                state.brightness = brightness;
+            }
+        }
+    }
+
+    /// Produce the colors to send to the strip by drawing each layer from the bottom up, then
+    /// applying the brightness of the strip and each segment followed by the calibration.
+    /// Brightness is applied at full precision so that dim colors can still be dithered.
+    pub fn compose(&self, calibration: &Calibration) -> Vec<Rgbw> {
+        let mut pixels = vec![[0, 0, 0, 0]; self.leds];
+        self.base.draw(&mut pixels);
+        self.animation.draw(&mut pixels);
+        for (_, overlay) in &self.overlays {
+            overlay.draw(&mut pixels);
+        }
+
+        for segment in &self.segments {
+            for pixel in &mut pixels[segment.range.clone()] {
+                for channel in pixel.iter_mut() {
+                    *channel = scale(*channel, segment.brightness);
+                }
+            }
+        }
+
+        for pixel in &mut pixels {
+            for channel in pixel.iter_mut() {
+                *channel = scale(*channel, self.brightness);
+            }
+            *pixel = calibration.apply(*pixel);
+        }
+
+        pixels
+    }
+}
+
+/// Scale a channel value by a brightness in the range 0-255
+fn scale(value: u16, brightness: u8) -> u16 {
+    (value as u32 * brightness as u32 / u8::MAX as u32) as u16
+}
@@ -0,0 +1,89 @@
+use super::color::Rgbw;
+use std::sync::Arc;
+
+/// Identifies one of the layers that get composited into a frame. Layers are drawn from the bottom
+/// up: the base, then the animation, then each overlay in the order it was created.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LayerId {
+    /// The static colors set over the API
+    #[default]
+    Base,
+    /// The colors drawn by running animations
+    Animation,
+    /// A named, transient layer drawn above everything else
+    Overlay(Arc<str>),
+}
+
+impl LayerId {
+    /// Parse the name of a layer from a request, an empty name refers to the base layer
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "" | "base" => LayerId::Base,
+            "animation" => LayerId::Animation,
+            name => LayerId::Overlay(name.into()),
+        }
+    }
+}
+
+/// How the colors of a layer are combined with the layers below it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blend {
+    /// Replace the colors below
+    #[default]
+    Normal,
+    /// Add to the colors below, saturating at full brightness
+    Add,
+    /// Multiply with the colors below, darkening them
+    Multiply,
+    /// Take the brighter value of each channel
+    Max,
+}
+
+impl Blend {
+    /// Combine a channel with the one below it
+    fn apply(self, below: u16, above: u16) -> u16 {
+        match self {
+            Blend::Normal => above,
+            Blend::Add => below.saturating_add(above),
+            Blend::Multiply => (below as u32 * above as u32 / u16::MAX as u32) as u16,
+            Blend::Max => below.max(above),
+        }
+    }
+}
+
+/// The contents of a single layer. Pixels which have not been set are transparent and let the
+/// layers below show through.
+#[derive(Debug)]
+pub(crate) struct Layer {
+    pub pixels: Vec<Option<Rgbw>>,
+    pub opacity: u8,
+    pub blend: Blend,
+}
+
+impl Layer {
+    /// Create a layer where every pixel is transparent
+    pub fn transparent(leds: usize) -> Self {
+        Layer {
+            pixels: vec![None; leds],
+            opacity: u8::MAX,
+            blend: Blend::Normal,
+        }
+    }
+
+    /// Draw the layer on top of the pixels below it
+    pub fn draw(&self, below: &mut [Rgbw]) {
+        if self.opacity == 0 {
+            return;
+        }
+
+        let layer = below.iter_mut().zip(&self.pixels);
+        for (below, above) in layer.filter_map(|(below, above)| Some((below, (*above)?))) {
+            for (below, above) in below.iter_mut().zip(above) {
+                let blended = self.blend.apply(*below, above) as i32;
+                let current = *below as i32;
+                *below =
+                    (current + (blended - current) * self.opacity as i32 / u8::MAX as i32) as u16;
+            }
+        }
+    }
+}
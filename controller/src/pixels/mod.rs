@@ -0,0 +1,690 @@
+use crate::{
+    config::{CaptureFormat, ColorOrder, Config, Segment},
+    errors::{CaptureError, ClipError, PixelsError},
+    interface::{self, Output},
+};
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender as MpscSender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{
+        oneshot::{self, Sender as OneshotSender},
+        watch,
+    },
+    task::{self, JoinHandle},
+    time,
+};
+use tracing::{error, info, instrument, Instrument};
+
+mod calibration;
+mod clip;
+mod color;
+mod dither;
+mod fade;
+mod frame;
+mod layer;
+mod power;
+mod stats;
+mod transition;
+
+use calibration::Calibration;
+pub use clip::Clip;
+use clip::{Playback, Recording};
+pub use color::Color;
+use color::{Rgbw, Rgbw8};
+use dither::Quantizer;
+use fade::Fade;
+use frame::Frame;
+pub use layer::{Blend, LayerId};
+use power::PowerLimiter;
+pub use power::PowerStatus;
+pub use stats::RenderStats;
+use stats::RenderTimer;
+use transition::Transition;
+
+/// The parts of the strip set over the API, without anything drawn by animations or overlays.
+/// Colors are stored as they are sent to the strip, as red, green, blue, and white.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub on: bool,
+    pub pixels: Vec<[u16; 4]>,
+    pub brightness: u8,
+    pub segments: BTreeMap<String, u8>,
+}
+
+/// The pixels an action applies to. Actions with a segment only affect that segment, otherwise
+/// they apply to the entire strip.
+#[derive(Clone, Debug, Default)]
+struct Target {
+    layer: LayerId,
+    segment: Option<usize>,
+}
+
+/// The possible actions can be applied to the lights
+#[derive(Debug)]
+enum Action {
+    /// Set the color of an individual pixel
+    Set {
+        target: Target,
+        index: u16,
+        color: Color,
+    },
+    /// Set the color of the entire strip
+    Fill { target: Target, color: Color },
+    /// Make the pixels of a layer transparent
+    Clear(Target),
+    /// Change how a layer is combined with the layers below it
+    ConfigureLayer {
+        layer: LayerId,
+        opacity: u8,
+        blend: Blend,
+    },
+    /// Remove an overlay layer
+    RemoveOverlay(Arc<str>),
+    /// Replace the base layer and brightness, crossfading to them over a duration
+    Restore {
+        snapshot: Snapshot,
+        transition: Duration,
+    },
+    /// Crossfade the strip or a segment from what it is showing over a duration
+    Transition {
+        segment: Option<usize>,
+        duration: Duration,
+    },
+    /// Set the brightness
+    Brightness { segment: Option<usize>, value: u8 },
+    /// Fade the strip on or off over a duration
+    Switch { on: bool, fade: Duration },
+    /// Whether colors should be sent to the strip without any calibration
+    BypassCalibration(bool),
+    /// Report the estimated power draw of the strip
+    Power(OneshotSender<PowerStatus>),
+    /// Report how closely rendering keeps up with the frame rate
+    RenderStats(OneshotSender<RenderStats>),
+    /// Start capturing the frames written to the strip
+    StartCapture(CaptureFormat, OneshotSender<Result<(), CaptureError>>),
+    /// Stop capturing frames and save them, reporting where they were saved
+    FinishCapture(OneshotSender<Result<PathBuf, CaptureError>>),
+    /// Start recording the frames written to the strip into a clip
+    StartRecording(OneshotSender<Result<(), ClipError>>),
+    /// Stop recording and produce the clip
+    FinishRecording(OneshotSender<Result<Clip, ClipError>>),
+    /// Play a clip in place of whatever the strip would otherwise show
+    PlayClip {
+        clip: Clip,
+        speed: f64,
+        repeat: bool,
+    },
+    /// Stop playing the current clip
+    StopClip,
+    /// Mark the changes as ready to be written to the strip on the next frame
+    Show,
+    /// Shutdown the pixel manager
+    Shutdown,
+}
+
+/// A user-friendly interface around the low-level controller. A handle can either address the
+/// entire strip or a single segment, in which case all indexes are relative to the segment. Each
+/// handle draws to a single layer, which is the base layer unless another one is selected.
+#[derive(Clone, Debug)]
+pub struct Pixels {
+    tx: MpscSender<Action>,
+    leds: u16,
+    segments: Arc<[Segment]>,
+    target: Target,
+    snapshots: watch::Receiver<Snapshot>,
+    switches: Arc<AtomicU64>,
+}
+
+impl Pixels {
+    /// Create a new connection to the light strip described by the configuration.
+    pub async fn new(config: &Config) -> Result<(Pixels, JoinHandle<()>), PixelsError> {
+        // Create the communication channels
+        let (err_tx, err_rx) = oneshot::channel();
+        let (tx, rx) = mpsc::sync_channel(5);
+        let frame = Frame::new(config.leds, &config.segments);
+        let (snapshot_tx, snapshots) = watch::channel(frame.snapshot(&config.segments, true));
+
+        // Spawn the manager
+        let manager_config = config.clone();
+        let handle = task::spawn_blocking(move || {
+            pixel_manager(manager_config, frame, rx, snapshot_tx, err_tx)
+        });
+
+        // Check if an error occurred while initializing the manager
+        if let Some(err) = err_rx.await.unwrap() {
+            Err(err)
+        } else {
+            let pixels = Pixels {
+                tx,
+                leds: config.leds,
+                segments: config.segments.clone().into(),
+                target: Target::default(),
+                snapshots,
+                switches: Arc::default(),
+            };
+            Ok((pixels, handle))
+        }
+    }
+
+    /// Get a handle which only addresses the named segment
+    pub fn segment(&self, name: &str) -> Option<Pixels> {
+        let id = self.segments.iter().position(|s| s.name == name)?;
+        let mut pixels = self.clone();
+        pixels.target.segment = Some(id);
+        Some(pixels)
+    }
+
+    /// Get a handle which draws to a different layer
+    pub fn layer(&self, layer: LayerId) -> Pixels {
+        let mut pixels = self.clone();
+        pixels.target.layer = layer;
+        pixels
+    }
+
+    /// The names of all the configured segments
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().map(|s| s.name.as_str())
+    }
+
+    /// The number of pixels addressable by this handle
+    pub fn length(&self) -> u16 {
+        match self.target.segment {
+            Some(id) => self.segments[id].length(),
+            None => self.leds,
+        }
+    }
+
+    /// The range of the strip addressable by this handle
+    pub fn bounds(&self) -> Range<u16> {
+        match self.target.segment {
+            Some(id) => self.segments[id].start..self.segments[id].end,
+            None => 0..self.leds,
+        }
+    }
+
+    /// Send an action to the manager
+    fn send(&self, action: Action) {
+        if let Err(err) = self.tx.send(action) {
+            error!(action = ?err.0, %err, "failed to send action");
+        }
+    }
+
+    /// Set the color of an individual pixel
+    #[instrument(skip(self))]
+    pub fn set(&self, index: u16, color: Color) {
+        self.send(Action::Set {
+            target: self.target.clone(),
+            index,
+            color,
+        })
+    }
+
+    /// Fill the entire strip or segment with the same color
+    #[instrument(skip(self))]
+    pub fn fill(&self, color: Color) {
+        self.send(Action::Fill {
+            target: self.target.clone(),
+            color,
+        })
+    }
+
+    /// Make the strip or segment transparent on this handle's layer, letting the layers below
+    /// show through. Clearing the base layer turns the pixels off instead.
+    #[instrument(skip(self))]
+    pub fn clear(&self) {
+        self.send(Action::Clear(self.target.clone()))
+    }
+
+    /// Change the opacity and blend mode of this handle's layer
+    #[instrument(skip(self))]
+    pub fn configure_layer(&self, opacity: u8, blend: Blend) {
+        self.send(Action::ConfigureLayer {
+            layer: self.target.layer.clone(),
+            opacity,
+            blend,
+        })
+    }
+
+    /// Remove an overlay along with all of its pixels
+    #[instrument(skip(self))]
+    pub fn remove_overlay(&self, name: &str) {
+        self.send(Action::RemoveOverlay(name.into()))
+    }
+
+    /// Replace the colors of the base layer and the brightness of the strip and its segments all
+    /// at once. The strip crossfades from what it was showing over the transition once the change
+    /// is shown.
+    #[instrument(skip(self, snapshot))]
+    pub fn restore(&self, snapshot: Snapshot, transition: Duration) {
+        self.send(Action::Restore {
+            snapshot,
+            transition,
+        })
+    }
+
+    /// Crossfade the strip or segment from what it is currently showing to whatever is shown
+    /// next over a duration, regardless of which layer changes
+    #[instrument(skip(self))]
+    pub fn transition(&self, duration: Duration) {
+        self.send(Action::Transition {
+            segment: self.target.segment,
+            duration,
+        })
+    }
+
+    /// Watch for changes to the base layer and brightness. A new snapshot is published each time
+    /// they are shown.
+    pub fn snapshots(&self) -> watch::Receiver<Snapshot> {
+        self.snapshots.clone()
+    }
+
+    /// Set the brightness of the strip or segment
+    #[instrument(skip(self))]
+    pub fn brightness(&self, value: u8) {
+        self.send(Action::Brightness {
+            segment: self.target.segment,
+            value,
+        })
+    }
+
+    /// Fade the strip to black. Changes made while the strip is off are remembered and shown once
+    /// it is turned back on.
+    #[instrument(skip(self))]
+    pub fn power_off(&self, fade: Duration) {
+        self.switch(false, fade);
+    }
+
+    /// Fade the strip back in to whatever it should currently be showing
+    #[instrument(skip(self))]
+    pub fn power_on(&self, fade: Duration) {
+        self.switch(true, fade);
+    }
+
+    /// Fade the strip on or off, returning the new power generation
+    fn switch(&self, on: bool, fade: Duration) -> u64 {
+        let generation = self.switches.fetch_add(1, Ordering::SeqCst) + 1;
+        self.send(Action::Switch { on, fade });
+        generation
+    }
+
+    /// Dim the strip like [`Pixels::power_off`], returning the power generation it leaves the strip
+    /// in. This can be compared against [`Pixels::power_generation`] to tell whether anything
+    /// else has switched the strip on or off since.
+    #[instrument(skip(self))]
+    pub fn dim(&self, fade: Duration) -> u64 {
+        self.switch(false, fade)
+    }
+
+    /// The number of times the strip has been switched on or off across all handles
+    pub fn power_generation(&self) -> u64 {
+        self.switches.load(Ordering::SeqCst)
+    }
+
+    /// Send colors to the strip exactly as they were set, without any calibration. This is
+    /// intended for tools that measure the raw output of the LEDs.
+    #[instrument(skip(self))]
+    pub fn bypass_calibration(&self, bypass: bool) {
+        self.send(Action::BypassCalibration(bypass))
+    }
+
+    /// Get the estimated power draw of the last frame sent to the strip
+    pub async fn power(&self) -> Option<PowerStatus> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Action::Power(tx));
+        rx.await.ok()
+    }
+
+    /// Capture the frames written to the strip over a duration and save them as an image, returning
+    /// where it was saved. Only the mock strip supports capturing frames.
+    #[instrument(skip(self))]
+    pub async fn capture(
+        &self,
+        duration: Duration,
+        format: CaptureFormat,
+    ) -> Result<PathBuf, CaptureError> {
+        // Run the capture in its own task so that it still finishes if the caller stops waiting,
+        // otherwise it would be left running and block any further captures
+        let pixels = self.clone();
+        let capture = task::spawn(
+            async move {
+                let (tx, rx) = oneshot::channel();
+                pixels.send(Action::StartCapture(format, tx));
+                rx.await.map_err(|_| CaptureError::Unavailable)??;
+
+                time::sleep(duration).await;
+
+                let (tx, rx) = oneshot::channel();
+                pixels.send(Action::FinishCapture(tx));
+                rx.await.map_err(|_| CaptureError::Unavailable)?
+            }
+            .in_current_span(),
+        );
+        capture.await.map_err(|_| CaptureError::Unavailable)?
+    }
+
+    /// Record the frames written to the strip over a duration into a clip
+    #[instrument(skip(self))]
+    pub async fn record(&self, duration: Duration) -> Result<Clip, ClipError> {
+        // Like captures, recordings run in their own task so they always finish
+        let pixels = self.clone();
+        let recording = task::spawn(
+            async move {
+                let (tx, rx) = oneshot::channel();
+                pixels.send(Action::StartRecording(tx));
+                rx.await.map_err(|_| ClipError::Unavailable)??;
+
+                time::sleep(duration).await;
+
+                let (tx, rx) = oneshot::channel();
+                pixels.send(Action::FinishRecording(tx));
+                rx.await.map_err(|_| ClipError::Unavailable)?
+            }
+            .in_current_span(),
+        );
+        recording.await.map_err(|_| ClipError::Unavailable)?
+    }
+
+    /// Play a clip on the entire strip, replacing whatever it would otherwise show until the clip
+    /// finishes or is stopped. The speed scales how fast the clip plays, and a repeating clip
+    /// loops until it is stopped.
+    #[instrument(skip(self, clip))]
+    pub fn play(&self, clip: Clip, speed: f64, repeat: bool) {
+        self.send(Action::PlayClip {
+            clip,
+            speed,
+            repeat,
+        })
+    }
+
+    /// Stop playing the current clip, if any
+    #[instrument(skip(self))]
+    pub fn stop_clip(&self) {
+        self.send(Action::StopClip)
+    }
+
+    /// Get statistics about the timing of renders
+    pub async fn render_stats(&self) -> Option<RenderStats> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Action::RenderStats(tx));
+        rx.await.ok()
+    }
+
+    /// Write any queued changes to the strip on the next frame
+    #[instrument(skip(self))]
+    pub fn show(&self) {
+        self.send(Action::Show)
+    }
+
+    /// Shutdown the manager
+    pub async fn shutdown(&self) {
+        self.send(Action::Shutdown)
+    }
+}
+
+/// Handle controlling the lights from a separate task
+#[instrument(skip_all)]
+fn pixel_manager(
+    config: Config,
+    mut frame: Frame,
+    actions: Receiver<Action>,
+    snapshots: watch::Sender<Snapshot>,
+    err_tx: OneshotSender<Option<PixelsError>>,
+) {
+    // Attempt to connect to the output
+    let mut output = match interface::connect(&config) {
+        Ok(output) => {
+            err_tx.send(None).unwrap();
+            output
+        }
+        // Report the failure back to the main task and exit
+        Err(e) => {
+            err_tx.send(Some(e)).unwrap();
+            return;
+        }
+    };
+
+    info!("pixel manager started");
+
+    let has_white = config.strip_type.has_white();
+    let mut calibration = Calibration::new(&config.calibration);
+    let mut limiter = PowerLimiter::new(&config.power, config.leds);
+    let mut quantizer = Quantizer::new(config.leds, config.render.dithering);
+    let mut timer = RenderTimer::default();
+    let mut fade = Fade::new();
+    let mut transition = Transition::new();
+    let mut recording: Option<Recording> = None;
+    let mut playback: Option<Playback> = None;
+
+    // Changes are only rendered at most once per frame, while dithering the last frame is
+    // re-rendered every frame so that the output averages out to the high precision colors
+    let interval = Duration::from_secs_f64(1.0 / config.render.max_fps as f64);
+    let dithering = config.render.dithering;
+    let mut pixels = frame.compose(&calibration);
+    let mut dirty = false;
+    let mut modified = false;
+    let mut scheduled = Instant::now();
+
+    // Handle incoming actions
+    loop {
+        let now = Instant::now();
+        let fading = fade.active(now) || transition.active(now);
+        let playing = playback.is_some();
+        let action = if dirty || dithering || fading || playing {
+            if now >= scheduled {
+                if dirty || fading || playing {
+                    // A clip replaces the frame until it finishes
+                    let clip = playback.as_ref().and_then(|playback| playback.frame(now));
+                    if clip.is_none() {
+                        playback = None;
+                    }
+
+                    pixels = clip.unwrap_or_else(|| frame.compose(&calibration));
+                    transition.apply(&mut pixels, now);
+                    fade.apply(&mut pixels, now);
+                    limiter.limit(&mut pixels);
+
+                    // Keep rendering until the fade and transition reach their final levels
+                    dirty = fading;
+                }
+
+                let start = Instant::now();
+                let leds = render(output.as_mut(), &mut quantizer, &pixels, config.color_order);
+                if let Some(recording) = &mut recording {
+                    recording.record(&leds);
+                }
+                timer.record(scheduled, start, Instant::now());
+
+                // Skip any frames that were missed rather than trying to catch up
+                scheduled += interval;
+                if scheduled < start {
+                    scheduled = start + interval;
+                }
+                continue;
+            }
+
+            match actions.recv_timeout(scheduled - now) {
+                Ok(action) => action,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match actions.recv() {
+                Ok(action) => action,
+                Err(_) => break,
+            }
+        };
+
+        // Track changes to the parts of the strip set over the API so they can be published
+        modified |= match &action {
+            Action::Set { target, .. } | Action::Fill { target, .. } | Action::Clear(target) => {
+                target.layer == LayerId::Base
+            }
+            Action::Brightness { .. } | Action::Restore { .. } | Action::Switch { .. } => true,
+            _ => false,
+        };
+
+        match action {
+            Action::Shutdown => break,
+            Action::Set {
+                target,
+                index,
+                color,
+            } => frame.set(
+                &target.layer,
+                target.segment,
+                index,
+                color.resolve(has_white),
+            ),
+            Action::Fill { target, color } => {
+                frame.fill(&target.layer, target.segment, color.resolve(has_white))
+            }
+            Action::Clear(target) => frame.clear(&target.layer, target.segment),
+            Action::ConfigureLayer {
+                layer,
+                opacity,
+                blend,
+            } => frame.configure(&layer, opacity, blend),
+            Action::RemoveOverlay(name) => frame.remove(&name),
+            Action::Restore {
+                snapshot,
+                transition: duration,
+            } => {
+                if !duration.is_zero() {
+                    let from = crossfade_from(&frame, &calibration, &transition);
+                    transition.start(from, 0..config.leds as usize, duration);
+                }
+
+                frame.restore(&snapshot, &config.segments)
+            }
+            Action::Transition { segment, duration } if !duration.is_zero() => {
+                let range = match segment {
+                    Some(id) => {
+                        let segment = &config.segments[id];
+                        segment.start as usize..segment.end as usize
+                    }
+                    None => 0..config.leds as usize,
+                };
+                let from = crossfade_from(&frame, &calibration, &transition);
+                transition.start(from, range, duration);
+            }
+            Action::Transition { .. } => {}
+            Action::Brightness { segment, value } => frame.brightness(segment, value),
+            Action::Switch { on, fade: duration } => fade.switch(on, duration),
+            Action::BypassCalibration(bypass) => calibration.set_bypass(bypass),
+            Action::Power(tx) => {
+                let status = PowerStatus {
+                    on: fade.is_on(),
+                    ..limiter.status()
+                };
+                let _ = tx.send(status);
+            }
+            Action::RenderStats(tx) => {
+                let _ = tx.send(timer.stats());
+            }
+            Action::StartCapture(format, tx) => {
+                let _ = tx.send(output.start_capture(format));
+            }
+            Action::FinishCapture(tx) => match output.finish_capture() {
+                // Encoding takes a while, so it happens off this thread to keep frames coming
+                Ok(recorder) => {
+                    task::spawn_blocking(move || {
+                        let _ = tx.send(recorder.save());
+                    });
+                }
+                Err(err) => {
+                    let _ = tx.send(Err(err));
+                }
+            },
+            Action::StartRecording(tx) => {
+                if recording.is_some() {
+                    let _ = tx.send(Err(ClipError::InProgress));
+                } else {
+                    recording = Some(Recording::new(config.leds, config.clips.max_frames));
+                    let _ = tx.send(Ok(()));
+
+                    // Render what is currently showing so the clip starts with it
+                    if !dirty && !dithering {
+                        scheduled = scheduled.max(Instant::now());
+                    }
+                    dirty = true;
+                }
+            }
+            Action::FinishRecording(tx) => {
+                let result = match recording.take() {
+                    Some(recording) => recording.finish(),
+                    None => Err(ClipError::Empty),
+                };
+                let _ = tx.send(result);
+            }
+            Action::PlayClip {
+                clip,
+                speed,
+                repeat,
+            } => {
+                if !dirty && !dithering && playback.is_none() {
+                    scheduled = scheduled.max(Instant::now());
+                }
+                playback = Some(Playback::new(clip, config.leds, speed, repeat));
+            }
+            Action::StopClip => {
+                if playback.take().is_some() {
+                    dirty = true;
+                }
+            }
+            Action::Show => {
+                timer.show();
+
+                // Don't count the time spent idle against the first frame
+                if !dirty && !dithering {
+                    scheduled = scheduled.max(Instant::now());
+                }
+                dirty = true;
+
+                if modified {
+                    snapshots.send_replace(frame.snapshot(&config.segments, fade.is_on()));
+                    modified = false;
+                }
+            }
+        }
+    }
+
+    info!("shutdown successfully");
+}
+
+/// Get the pixels a new transition starts from, which is whatever is currently showing even if it
+/// is part way through another transition
+fn crossfade_from(frame: &Frame, calibration: &Calibration, transition: &Transition) -> Vec<Rgbw> {
+    let mut from = frame.compose(calibration);
+    transition.apply(&mut from, Instant::now());
+    from
+}
+
+/// Reduce a frame to the precision of the LEDs and write it to the strip, returning the colors
+/// that were written
+fn render(
+    output: &mut dyn Output,
+    quantizer: &mut Quantizer,
+    pixels: &[Rgbw],
+    order: ColorOrder,
+) -> Vec<Rgbw8> {
+    let pixels = quantizer.apply(pixels);
+    let leds = pixels
+        .iter()
+        .map(|&pixel| color::pack(pixel, order))
+        .collect::<Vec<_>>();
+
+    if let Err(err) = output.render(&leds) {
+        error!(%err, "failed to commit changes");
+    }
+
+    pixels
+}
@@ -0,0 +1,92 @@
+use super::color::Rgbw;
+use crate::config;
+use tracing::{debug, info, warn};
+
+/// The estimated power draw of the strip
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PowerStatus {
+    /// Whether the strip is turned on
+    pub on: bool,
+    /// The most current the strip is allowed to draw in milliamps, if limiting is enabled
+    pub budget: Option<u32>,
+    /// The current the last frame would have drawn without limiting in milliamps
+    pub estimated: u32,
+    /// The current the last frame actually drew in milliamps
+    pub drawn: u32,
+    /// Whether the last frame had to be dimmed to stay within the budget
+    pub limiting: bool,
+    /// The number of times the strip has started being limited
+    pub events: u64,
+}
+
+/// Dims frames which would draw more current than the power supply can provide
+#[derive(Debug)]
+pub(crate) struct PowerLimiter {
+    budget: Option<f32>,
+    channels: [f32; 4],
+    idle: f32,
+    status: PowerStatus,
+}
+
+impl PowerLimiter {
+    /// Create a limiter for a strip with the given number of LEDs
+    pub fn new(config: &config::Power, leds: u16) -> Self {
+        let milliamps = &config.milliamps;
+        let budget = config.budget.map(|amps| amps * 1000.0);
+
+        PowerLimiter {
+            budget,
+            channels: [milliamps.r, milliamps.g, milliamps.b, milliamps.w],
+            idle: config.idle * leds as f32,
+            status: PowerStatus {
+                budget: budget.map(|b| b as u32),
+                ..PowerStatus::default()
+            },
+        }
+    }
+
+    /// Estimate the current drawn by the color channels of a frame in milliamps
+    fn estimate(&self, pixels: &[Rgbw]) -> f32 {
+        pixels
+            .iter()
+            .flat_map(|pixel| pixel.iter().zip(self.channels))
+            .map(|(&value, milliamps)| value as f32 / u16::MAX as f32 * milliamps)
+            .sum()
+    }
+
+    /// Scale down the frame if it would exceed the budget
+    pub fn limit(&mut self, pixels: &mut [Rgbw]) {
+        let channels = self.estimate(pixels);
+        let estimated = self.idle + channels;
+
+        let drawn = match self.budget {
+            Some(budget) if estimated > budget => {
+                let factor = ((budget - self.idle) / channels).clamp(0.0, 1.0);
+                for channel in pixels.iter_mut().flatten() {
+                    *channel = (*channel as f32 * factor) as u16;
+                }
+
+                self.idle + channels * factor
+            }
+            _ => estimated,
+        };
+        let limiting = drawn < estimated;
+
+        debug!(%estimated, %drawn, "estimated power draw");
+        if limiting && !self.status.limiting {
+            self.status.events += 1;
+            warn!(%estimated, budget = ?self.budget, "power draw exceeds budget, limiting brightness");
+        } else if !limiting && self.status.limiting {
+            info!(%estimated, "power draw within budget, no longer limiting");
+        }
+
+        self.status.estimated = estimated as u32;
+        self.status.drawn = drawn as u32;
+        self.status.limiting = limiting;
+    }
+
+    /// The power draw of the last frame
+    pub fn status(&self) -> PowerStatus {
+        self.status
+    }
+}
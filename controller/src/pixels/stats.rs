@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+/// Statistics about how closely rendering keeps up with the frame rate. Durations are in
+/// microseconds.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    /// The number of frames written to the strip
+    pub frames: u64,
+    /// The number of times changes were requested to be shown
+    pub shows: u64,
+    /// The average delay between when a frame was scheduled and when it was rendered
+    pub mean_lateness: u64,
+    /// The standard deviation of the delay between scheduling and rendering
+    pub jitter: u64,
+    /// The longest delay between scheduling and rendering
+    pub max_lateness: u64,
+    /// The average time spent writing a frame to the strip
+    pub mean_render_time: u64,
+    /// The longest time spent writing a frame to the strip
+    pub max_render_time: u64,
+}
+
+/// Collects the timing of each render. The variance of the lateness is tracked using Welford's
+/// algorithm so that no history needs to be kept.
+#[derive(Debug, Default)]
+pub(crate) struct RenderTimer {
+    frames: u64,
+    shows: u64,
+    mean: f64,
+    m2: f64,
+    max_lateness: Duration,
+    total_render_time: Duration,
+    max_render_time: Duration,
+}
+
+impl RenderTimer {
+    /// Count a request to show changes
+    pub fn show(&mut self) {
+        self.shows += 1;
+    }
+
+    /// Record a frame which was scheduled to be rendered at a point in time
+    pub fn record(&mut self, scheduled: Instant, start: Instant, end: Instant) {
+        let lateness = start.saturating_duration_since(scheduled);
+        let render_time = end.saturating_duration_since(start);
+        trace!(?lateness, ?render_time, "rendered frame");
+
+        self.frames += 1;
+        let value = lateness.as_secs_f64() * 1e6;
+        let delta = value - self.mean;
+        self.mean += delta / self.frames as f64;
+        self.m2 += delta * (value - self.mean);
+
+        self.max_lateness = self.max_lateness.max(lateness);
+        self.total_render_time += render_time;
+        self.max_render_time = self.max_render_time.max(render_time);
+    }
+
+    /// Summarize the recorded timings
+    pub fn stats(&self) -> RenderStats {
+        let (jitter, mean_render_time) = match self.frames {
+            0 => (0.0, Duration::ZERO),
+            frames => (
+                (self.m2 / frames as f64).sqrt(),
+                self.total_render_time.div_f64(frames as f64),
+            ),
+        };
+
+        RenderStats {
+            frames: self.frames,
+            shows: self.shows,
+            mean_lateness: self.mean as u64,
+            jitter: jitter as u64,
+            max_lateness: self.max_lateness.as_micros() as u64,
+            mean_render_time: mean_render_time.as_micros() as u64,
+            max_render_time: self.max_render_time.as_micros() as u64,
+        }
+    }
+}
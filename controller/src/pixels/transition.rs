@@ -0,0 +1,58 @@
+use super::color::Rgbw;
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// Crossfades the output of the strip, or part of it, from what it was showing to the current
+/// frame. The frame keeps being updated during the transition, so animations blend in as they run.
+#[derive(Debug)]
+pub(crate) struct Transition {
+    from: Vec<Rgbw>,
+    range: Range<usize>,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Transition {
+    /// Create a transition which has already finished
+    pub fn new() -> Self {
+        Transition {
+            from: Vec::new(),
+            range: 0..0,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Start crossfading a range of pixels from what was showing over a duration
+    pub fn start(&mut self, from: Vec<Rgbw>, range: Range<usize>, duration: Duration) {
+        self.from = from;
+        self.range = range;
+        self.start = Instant::now();
+        self.duration = duration;
+    }
+
+    /// Whether the output is still changing
+    pub fn active(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) < self.duration
+    }
+
+    /// Mix the frame with the pixels that were showing according to how far along the
+    /// transition is
+    pub fn apply(&self, pixels: &mut [Rgbw], now: Instant) {
+        if !self.active(now) {
+            return;
+        }
+
+        let elapsed = now.saturating_duration_since(self.start);
+        let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let range = self.range.clone();
+        for (pixel, from) in pixels[range.clone()].iter_mut().zip(&self.from[range]) {
+            for (channel, &from) in pixel.iter_mut().zip(from) {
+                let from = from as f32;
+                *channel = (from + (*channel as f32 - from) * progress) as u16;
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{
+    animations::{Animator, Pause},
+    pixels::Pixels,
+};
+use std::time::Duration;
+use tracing::instrument;
+
+/// Fade the strip to black and pause any running animations where they are
+#[instrument(skip(pixels, animator))]
+pub async fn off(pixels: &Pixels, animator: &Animator, fade: Duration) {
+    animator.pause(Pause::PowerOff).await;
+    pixels.power_off(fade);
+    pixels.show();
+}
+
+/// Fade the strip back in and resume any paused animations
+#[instrument(skip(pixels, animator))]
+pub async fn on(pixels: &Pixels, animator: &Animator, fade: Duration) {
+    pixels.power_on(fade);
+    pixels.show();
+    animator.resume(Pause::PowerOff).await;
+}
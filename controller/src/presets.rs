@@ -0,0 +1,124 @@
+use crate::{
+    animations::SharedAnimator,
+    config::Config,
+    errors::{StateError, UnknownPreset},
+    pixels::{Pixels, Snapshot},
+    state,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+use tracing::{info, instrument, warn};
+
+/// A named arrangement of the strip that can be applied all at once. Colors are stored as they
+/// are sent to the strip, as red, green, blue, and white.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Preset {
+    pub brightness: u8,
+    /// The animation to run across the entire strip
+    pub animation: Option<String>,
+    pub pixels: Vec<[u16; 4]>,
+    /// The brightness of each segment
+    pub segments: BTreeMap<String, u8>,
+}
+
+type Stored = BTreeMap<String, Preset>;
+
+/// Saves and applies presets, which are kept next to the animations
+#[derive(Clone, Debug)]
+pub struct Presets {
+    path: PathBuf,
+    presets: Arc<Mutex<Stored>>,
+    pixels: Pixels,
+    animator: SharedAnimator,
+}
+
+impl Presets {
+    /// Load the saved presets
+    pub async fn load(
+        config: &Config,
+        pixels: Pixels,
+        animator: SharedAnimator,
+    ) -> Result<Presets, StateError> {
+        let path = config.presets_path();
+        let presets = state::load::<Stored>(&path).await?.unwrap_or_default();
+        info!(count = %presets.len(), "loaded presets");
+
+        Ok(Presets {
+            path,
+            presets: Arc::new(Mutex::new(presets)),
+            pixels,
+            animator,
+        })
+    }
+
+    /// Save what the strip is currently showing as a preset, replacing any existing preset with
+    /// the same name
+    #[instrument(skip(self))]
+    pub async fn save(&self, name: &str) -> Result<(), StateError> {
+        let snapshot = self.pixels.snapshots().borrow().clone();
+        let animation = self.animator.running().borrow().get(&None).cloned();
+        let preset = Preset {
+            brightness: snapshot.brightness,
+            animation,
+            pixels: snapshot.pixels,
+            segments: snapshot.segments,
+        };
+
+        let mut presets = self.presets.lock().await;
+        presets.insert(name.to_owned(), preset);
+        state::save(&*presets, &self.path).await
+    }
+
+    /// Show a preset on the strip, crossfading to it over the transition. Any running animations
+    /// are replaced by the preset's animation, if it has one.
+    #[instrument(skip(self))]
+    pub async fn apply(&self, name: &str, transition: Duration) -> Result<(), UnknownPreset> {
+        let preset = self
+            .presets
+            .lock()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| UnknownPreset(name.to_owned()))?;
+
+        // Stopping waits for the animations to be cleared, so the new colors are shown in the same
+        // frame the animations disappear in. With a transition, they are cleared as the crossfade
+        // starts instead.
+        self.pixels.restore(
+            Snapshot {
+                on: true,
+                pixels: preset.pixels,
+                brightness: preset.brightness,
+                segments: preset.segments,
+            },
+            transition,
+        );
+        self.animator.stop_all().await;
+        self.pixels.show();
+
+        if let Some(id) = &preset.animation {
+            if let Err(err) = self.animator.start(None, id).await {
+                warn!(%err, %id, "could not start animation");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove a preset by name. This method is idempotent.
+    #[instrument(skip(self))]
+    pub async fn delete(&self, name: &str) -> Result<(), StateError> {
+        let mut presets = self.presets.lock().await;
+        if presets.remove(name).is_some() {
+            state::save(&*presets, &self.path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get all the presets by name
+    pub async fn list(&self) -> Stored {
+        self.presets.lock().await.clone()
+    }
+}
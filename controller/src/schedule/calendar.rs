@@ -0,0 +1,208 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A date and time on the calendar without any timezone information
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub date: Date,
+    /// Seconds since midnight
+    pub seconds: u32,
+}
+
+/// A day on the proleptic Gregorian calendar
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    /// The month of the year, 1-12
+    pub month: u8,
+    /// The day of the month, 1-31
+    pub day: u8,
+}
+
+impl Date {
+    /// Create a date from the number of days since the Unix epoch
+    pub fn from_days(days: i64) -> Self {
+        // From http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+
+        Date { year, month, day }
+    }
+
+    /// The number of days since the Unix epoch
+    pub fn days(&self) -> i64 {
+        // From http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = self.year as i64 - (self.month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = self.month as i64;
+        let doy =
+            (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+
+    /// The day of the week, where Sunday is 0
+    pub fn weekday(&self) -> u8 {
+        (self.days() + 4).rem_euclid(7) as u8
+    }
+
+    /// The number of days in the month
+    pub fn days_in_month(year: i32, month: u8) -> u8 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// The next day on the calendar
+    pub fn succ(&self) -> Self {
+        Date::from_days(self.days() + 1)
+    }
+}
+
+impl DateTime {
+    /// Create a date and time from its parts
+    #[cfg(test)]
+    pub fn new(year: i32, month: u8, day: u8, hour: u32, minute: u32) -> Self {
+        DateTime {
+            date: Date { year, month, day },
+            seconds: hour * 3600 + minute * 60,
+        }
+    }
+
+    /// Create a date and time from a number of seconds since the Unix epoch
+    pub fn from_timestamp(timestamp: i64) -> Self {
+        DateTime {
+            date: Date::from_days(timestamp.div_euclid(SECONDS_PER_DAY)),
+            seconds: timestamp.rem_euclid(SECONDS_PER_DAY) as u32,
+        }
+    }
+
+    /// The number of seconds since the Unix epoch, treating the date and time as UTC
+    pub fn timestamp(&self) -> i64 {
+        self.date.days() * SECONDS_PER_DAY + self.seconds as i64
+    }
+
+    /// The hour of the day, 0-23
+    pub fn hour(&self) -> u8 {
+        (self.seconds / 3600) as u8
+    }
+
+    /// The minute of the hour, 0-59
+    pub fn minute(&self) -> u8 {
+        (self.seconds / 60 % 60) as u8
+    }
+}
+
+/// Convert a point in time to the number of seconds since the Unix epoch
+pub fn timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// Convert a number of seconds since the Unix epoch to a point in time
+pub fn system_time(timestamp: i64) -> SystemTime {
+    if timestamp >= 0 {
+        UNIX_EPOCH + Duration::from_secs(timestamp as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(timestamp.unsigned_abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        let epoch = Date::from_days(0);
+        assert_eq!(
+            epoch,
+            Date {
+                year: 1970,
+                month: 1,
+                day: 1
+            }
+        );
+        assert_eq!(epoch.weekday(), 4);
+
+        let leap = Date {
+            year: 2000,
+            month: 2,
+            day: 29,
+        };
+        assert_eq!(leap.days(), 11_016);
+        assert_eq!(leap.weekday(), 2);
+
+        let before = Date {
+            year: 1969,
+            month: 12,
+            day: 31,
+        };
+        assert_eq!(before.days(), -1);
+        assert_eq!(
+            Date::from_days(19_723),
+            Date {
+                year: 2024,
+                month: 1,
+                day: 1
+            }
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        // Roughly 2700 years either side of the epoch
+        let mut previous = Date::from_days(-1_000_001);
+        for days in -1_000_000..1_000_000 {
+            let date = Date::from_days(days);
+            assert_eq!(date.days(), days, "{:?}", date);
+
+            // Each day follows on from the one before
+            if date.day == 1 {
+                assert_eq!(
+                    previous.day,
+                    Date::days_in_month(previous.year, previous.month)
+                );
+                assert_eq!(date.month, previous.month % 12 + 1);
+            } else {
+                assert_eq!(date.day, previous.day + 1);
+                assert_eq!(date.month, previous.month);
+            }
+            previous = date;
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(Date::days_in_month(2024, 2), 29);
+        assert_eq!(Date::days_in_month(2023, 2), 28);
+        assert_eq!(Date::days_in_month(2000, 2), 29);
+        assert_eq!(Date::days_in_month(2100, 2), 28);
+    }
+
+    #[test]
+    fn timestamps() {
+        let time = DateTime::from_timestamp(-1);
+        assert_eq!(time.date.days(), -1);
+        assert_eq!((time.hour(), time.minute()), (23, 59));
+        assert_eq!(time.timestamp(), -1);
+
+        assert_eq!(timestamp(system_time(-86_400)), -86_400);
+        assert_eq!(timestamp(system_time(1_700_000_000)), 1_700_000_000);
+    }
+}
@@ -0,0 +1,341 @@
+use super::{
+    calendar::{Date, DateTime},
+    timezone::TimeZone,
+};
+use crate::errors::InvalidCron;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// How far ahead to look for the next time an expression matches. Expressions for February 29th
+/// can go almost 8 years without matching when a century skips its leap year.
+const SEARCH_DAYS: i64 = 366 * 8;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron expression made of five fields: minute, hour, day of the month, month, and day of the
+/// week. Each field accepts `*`, single values, ranges like `1-5`, steps like `*/15` or `0-30/5`,
+/// and comma separated lists of any of those. Months and days of the week can also be given by
+/// their three letter names.
+///
+/// Like most cron implementations, when both the day of the month and the day of the week are
+/// restricted, the expression matches days where either one matches.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cron {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// Whether the expression matches a date
+    fn matches_date(&self, date: &Date) -> bool {
+        let day = contains(self.days, date.day);
+        let weekday = contains(self.weekdays, date.weekday());
+        let day = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+
+        day && contains(self.months, date.month)
+    }
+
+    /// Whether the expression fires at the start of a minute. Like [`Cron::next`], times skipped
+    /// when the clocks go forward fire at the equivalent standard time and times repeated when
+    /// they go back only fire once.
+    pub fn matches(&self, minute: i64, timezone: &TimeZone) -> bool {
+        self.next(minute - 1, timezone) == Some(minute)
+    }
+
+    /// Find the next time the expression matches after a point in time, as seconds since the Unix
+    /// epoch
+    pub fn next(&self, after: i64, timezone: &TimeZone) -> Option<i64> {
+        let start = timezone.local(after).date.days();
+        (start..start + SEARCH_DAYS)
+            .map(Date::from_days)
+            .filter(|date| self.matches_date(date))
+            .flat_map(|date| {
+                values(self.hours, 24).flat_map(move |hour| {
+                    values(self.minutes, 60).map(move |minute| DateTime {
+                        date,
+                        seconds: hour as u32 * 3600 + minute as u32 * 60,
+                    })
+                })
+            })
+            .map(|local| timezone.utc(local))
+            .find(|&time| time > after)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = InvalidCron;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = match fields[..] {
+            [a, b, c, d, e] => [a, b, c, d, e],
+            _ => return Err(InvalidCron("expected 5 fields".into())),
+        };
+
+        // Sunday can be given as either 0 or 7
+        let mut weekday_bits = field(weekdays, 0, 7, &WEEKDAYS, "day of the week")?;
+        if contains(weekday_bits, 7) {
+            weekday_bits |= 1;
+        }
+
+        Ok(Cron {
+            source: fields.join(" "),
+            minutes: field(minutes, 0, 59, &[], "minute")?,
+            hours: field(hours, 0, 23, &[], "hour")?,
+            days: field(days, 1, 31, &[], "day of the month")?,
+            months: field(months, 1, 12, &MONTHS, "month")?,
+            weekdays: weekday_bits,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = InvalidCron;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Cron> for String {
+    fn from(cron: Cron) -> Self {
+        cron.source
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Whether a value is set in a field
+fn contains(bits: u64, value: u8) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Iterate over the values set in a field
+fn values(bits: u64, limit: u8) -> impl Iterator<Item = u8> {
+    (0..limit).filter(move |&value| contains(bits, value))
+}
+
+/// Parse a single field into a set of bits, one for each value it matches
+fn field(s: &str, min: u8, max: u8, names: &[&str], label: &str) -> Result<u64, InvalidCron> {
+    let invalid = || InvalidCron(format!("invalid {label} {s:?}"));
+    let value = |v: &str| -> Result<u8, InvalidCron> {
+        // Names start counting from the minimum value
+        let value = match names.iter().position(|n| n.eq_ignore_ascii_case(v)) {
+            Some(position) => position as u8 + min,
+            None => v.parse().map_err(|_| invalid())?,
+        };
+
+        if (min..=max).contains(&value) {
+            Ok(value)
+        } else {
+            Err(InvalidCron(format!(
+                "{label} must be between {min} and {max}"
+            )))
+        }
+    };
+
+    let mut bits = 0;
+    for part in s.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u8>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => return Err(invalid()),
+            },
+            None => (part, None),
+        };
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // A single value with a step runs until the end of the range
+            None if step.is_some() => (value(range)?, max),
+            None => {
+                let value = value(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The values set in a field
+    fn set(bits: u64, limit: u8) -> Vec<u8> {
+        values(bits, limit).collect()
+    }
+
+    #[test]
+    fn field_steps() {
+        let minutes = field("*/15", 0, 59, &[], "minute").unwrap();
+        assert_eq!(set(minutes, 60), [0, 15, 30, 45]);
+
+        let minutes = field("5/10", 0, 59, &[], "minute").unwrap();
+        assert_eq!(set(minutes, 60), [5, 15, 25, 35, 45, 55]);
+
+        let hours = field("0-12/4,23", 0, 23, &[], "hour").unwrap();
+        assert_eq!(set(hours, 24), [0, 4, 8, 12, 23]);
+    }
+
+    #[test]
+    fn field_names() {
+        let weekdays = field("mon-fri", 0, 7, &WEEKDAYS, "day of the week").unwrap();
+        assert_eq!(set(weekdays, 8), [1, 2, 3, 4, 5]);
+
+        let months = field("JAN,Dec", 1, 12, &MONTHS, "month").unwrap();
+        assert_eq!(set(months, 13), [1, 12]);
+    }
+
+    #[test]
+    fn seven_is_sunday() {
+        let cron = "0 0 * * 7".parse::<Cron>().unwrap();
+        assert!(contains(cron.weekdays, 0));
+
+        let cron = "0 0 * * 5-7".parse::<Cron>().unwrap();
+        assert!(contains(cron.weekdays, 0));
+        assert!(contains(cron.weekdays, 5));
+        assert!(!contains(cron.weekdays, 1));
+    }
+
+    #[test]
+    fn field_rejects_invalid() {
+        assert!(field("5-1", 0, 59, &[], "minute").is_err());
+        assert!(field("*/0", 0, 59, &[], "minute").is_err());
+        assert!(field("1-5/0", 0, 59, &[], "minute").is_err());
+        assert!(field("60", 0, 59, &[], "minute").is_err());
+        assert!(field("0", 1, 31, &[], "day of the month").is_err());
+        assert!(field("fri-mon", 0, 7, &WEEKDAYS, "day of the week").is_err());
+        assert!(field("", 0, 59, &[], "minute").is_err());
+        assert!("0 0 * *".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        let friday_13 = Date {
+            year: 2023,
+            month: 10,
+            day: 13,
+        };
+        let saturday_14 = Date {
+            year: 2023,
+            month: 10,
+            day: 14,
+        };
+        let friday_20 = Date {
+            year: 2023,
+            month: 10,
+            day: 20,
+        };
+        let monday_13 = Date {
+            year: 2023,
+            month: 11,
+            day: 13,
+        };
+
+        // When both are restricted, either one matching is enough
+        let either = "0 0 13 * fri".parse::<Cron>().unwrap();
+        assert!(either.matches_date(&friday_13));
+        assert!(either.matches_date(&friday_20));
+        assert!(either.matches_date(&monday_13));
+        assert!(!either.matches_date(&saturday_14));
+
+        // Otherwise only the restricted one counts
+        let weekday = "0 0 * * fri".parse::<Cron>().unwrap();
+        assert!(weekday.matches_date(&friday_20));
+        assert!(!weekday.matches_date(&monday_13));
+
+        let day = "0 0 13 * *".parse::<Cron>().unwrap();
+        assert!(day.matches_date(&monday_13));
+        assert!(!day.matches_date(&friday_20));
+    }
+
+    #[test]
+    fn next_leap_day() {
+        let cron = "0 12 29 2 *".parse::<Cron>().unwrap();
+        assert_eq!(
+            cron.next(DateTime::new(2021, 3, 1, 0, 0).timestamp(), &TimeZone::UTC),
+            Some(DateTime::new(2024, 2, 29, 12, 0).timestamp())
+        );
+
+        // 2100 is not a leap year, so the next one is 8 years later
+        assert_eq!(
+            cron.next(DateTime::new(2096, 3, 1, 0, 0).timestamp(), &TimeZone::UTC),
+            Some(DateTime::new(2104, 2, 29, 12, 0).timestamp())
+        );
+    }
+
+    #[test]
+    fn next_spring_forward() {
+        let timezone = "EST5EDT,M3.2.0,M11.1.0".parse::<TimeZone>().unwrap();
+        let cron = "30 2 * * *".parse::<Cron>().unwrap();
+
+        // 02:30 is skipped when the clocks go forward on March 12th, 2023, so it runs at the
+        // equivalent standard time instead, which is 03:30 daylight time
+        let midnight = DateTime::new(2023, 3, 12, 5, 0).timestamp();
+        let skipped = cron.next(midnight, &timezone).unwrap();
+        assert_eq!(skipped, DateTime::new(2023, 3, 12, 7, 30).timestamp());
+        assert_eq!(timezone.local(skipped).hour(), 3);
+
+        // The following day is back to normal, in daylight time
+        assert_eq!(
+            cron.next(skipped, &timezone),
+            Some(DateTime::new(2023, 3, 13, 6, 30).timestamp())
+        );
+    }
+
+    #[test]
+    fn next_fall_back() {
+        let timezone = "EST5EDT,M3.2.0,M11.1.0".parse::<TimeZone>().unwrap();
+        let cron = "30 1 * * *".parse::<Cron>().unwrap();
+
+        // 01:30 happens twice when the clocks go back on November 5th, 2023, but only runs the
+        // first time
+        let midnight = DateTime::new(2023, 11, 5, 4, 0).timestamp();
+        let first = cron.next(midnight, &timezone).unwrap();
+        assert_eq!(first, DateTime::new(2023, 11, 5, 5, 30).timestamp());
+        assert_eq!(
+            cron.next(first, &timezone),
+            Some(DateTime::new(2023, 11, 6, 6, 30).timestamp())
+        );
+    }
+
+    #[test]
+    fn next_is_after() {
+        let cron = "*/15 * * * *".parse::<Cron>().unwrap();
+        let start = DateTime::new(2023, 12, 31, 23, 45).timestamp();
+        assert_eq!(
+            cron.next(start, &TimeZone::UTC),
+            Some(DateTime::new(2024, 1, 1, 0, 0).timestamp())
+        );
+        assert_eq!(cron.next(start - 1, &TimeZone::UTC), Some(start));
+    }
+}
@@ -0,0 +1,387 @@
+use crate::{
+    animations::{Animator, SharedAnimator},
+    config::Config,
+    errors::{ActionError, StateError, UnknownSegment},
+    pixels::{Color, Pixels},
+    presets::Presets,
+    state,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::{Mutex, Notify},
+    task::{self, JoinHandle},
+    time,
+};
+use tracing::{info, info_span, instrument, warn, Instrument};
+
+mod calendar;
+mod cron;
+mod sun;
+mod timezone;
+
+pub use cron::Cron;
+pub use sun::{Event, Location, Sun};
+pub use timezone::TimeZone;
+
+/// A recurring action run by the controller
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Schedule {
+    /// Whether the schedule should run
+    pub enabled: bool,
+    /// When the schedule runs
+    #[serde(flatten)]
+    pub trigger: Trigger,
+    /// What happens when the schedule runs
+    pub action: Action,
+}
+
+/// The times a schedule can run at
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// Whenever a cron expression matches
+    Cron(Cron),
+    /// Some time before or after sunrise or sunset each day
+    Sun(Sun),
+}
+
+impl Trigger {
+    /// Whether the trigger fires at the start of a minute, given as seconds since the Unix epoch.
+    /// Triggers relative to the sun never fire without a location.
+    fn matches(&self, minute: i64, location: Option<&Location>, timezone: &TimeZone) -> bool {
+        match (self, location) {
+            (Trigger::Cron(cron), _) => cron.matches(minute, timezone),
+            (Trigger::Sun(sun), Some(location)) => sun.matches(minute, location, timezone),
+            (Trigger::Sun(_), None) => false,
+        }
+    }
+
+    /// Find the next time the trigger fires after a point in time, as seconds since the Unix epoch
+    fn next(&self, after: i64, location: Option<&Location>, timezone: &TimeZone) -> Option<i64> {
+        match (self, location) {
+            (Trigger::Cron(cron), _) => cron.next(after, timezone),
+            (Trigger::Sun(sun), Some(location)) => sun.next(after, location, timezone),
+            (Trigger::Sun(_), None) => None,
+        }
+    }
+}
+
+/// The actions that can be scheduled. Actions with a segment only affect that segment, otherwise
+/// they apply to the entire strip.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// Fill with a color
+    Fill {
+        color: Color,
+        segment: Option<String>,
+    },
+    /// Change the brightness
+    Brightness { value: u8, segment: Option<String> },
+    /// Start an animation by id
+    StartAnimation { id: String, segment: Option<String> },
+    /// Stop the running animation
+    StopAnimation { segment: Option<String> },
+    /// Apply a preset, crossfading to it over a number of milliseconds
+    ApplyPreset {
+        name: String,
+        #[serde(default)]
+        transition: u64,
+    },
+}
+
+impl Action {
+    /// Perform the action on the strip
+    async fn run(
+        &self,
+        pixels: &Pixels,
+        animator: &Animator,
+        presets: &Presets,
+    ) -> Result<(), ActionError> {
+        let segment = |segment: &Option<String>| match segment {
+            Some(name) => pixels
+                .segment(name)
+                .ok_or_else(|| UnknownSegment(name.clone())),
+            None => Ok(pixels.clone()),
+        };
+
+        match self {
+            Action::Fill { color, segment: s } => {
+                let pixels = segment(s)?;
+                pixels.fill(*color);
+                pixels.show();
+            }
+            Action::Brightness { value, segment: s } => {
+                let pixels = segment(s)?;
+                pixels.brightness(*value);
+                pixels.show();
+            }
+            Action::StartAnimation { id, segment } => {
+                animator.start(segment.as_deref(), id).await?
+            }
+            Action::StopAnimation { segment } => animator.stop(segment.as_deref()).await?,
+            Action::ApplyPreset { name, transition } => {
+                presets
+                    .apply(name, Duration::from_millis(*transition))
+                    .await?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A schedule along with when it runs next
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub schedule: Schedule,
+    pub next: Option<SystemTime>,
+}
+
+type Schedules = BTreeMap<String, Schedule>;
+
+/// Runs actions at recurring times, even when nothing else is connected to the controller.
+/// Schedules are checked at the start of every minute in the configured timezone and saved next to
+/// the animations whenever they change.
+#[derive(Clone, Debug)]
+pub struct Scheduler {
+    path: PathBuf,
+    timezone: TimeZone,
+    location: Option<Location>,
+    schedules: Arc<Mutex<Schedules>>,
+    shutdown: Arc<Notify>,
+}
+
+impl Scheduler {
+    /// Load the saved schedules and start running them
+    pub async fn new(
+        config: &Config,
+        pixels: Pixels,
+        animator: SharedAnimator,
+        presets: Presets,
+    ) -> Result<(Scheduler, JoinHandle<()>), StateError> {
+        let path = config.schedules_path();
+        let schedules = state::load::<Schedules>(&path).await?.unwrap_or_default();
+        info!(count = %schedules.len(), "loaded schedules");
+
+        let scheduler = Scheduler {
+            path,
+            timezone: config.scheduler.timezone.clone(),
+            location: config.scheduler.location,
+            schedules: Arc::new(Mutex::new(schedules)),
+            shutdown: Arc::new(Notify::new()),
+        };
+
+        let runner = runner(scheduler.clone(), pixels, animator, presets);
+        let handle = task::spawn(runner.instrument(info_span!("scheduler")));
+
+        Ok((scheduler, handle))
+    }
+
+    /// Add a schedule, replacing any existing schedule with the same name
+    #[instrument(skip(self, schedule))]
+    pub async fn add(&self, name: &str, schedule: Schedule) -> Result<(), StateError> {
+        let mut schedules = self.schedules.lock().await;
+        schedules.insert(name.to_owned(), schedule);
+        state::save(&*schedules, &self.path).await
+    }
+
+    /// Remove a schedule by name. This method is idempotent.
+    #[instrument(skip(self))]
+    pub async fn remove(&self, name: &str) -> Result<(), StateError> {
+        let mut schedules = self.schedules.lock().await;
+        if schedules.remove(name).is_some() {
+            state::save(&*schedules, &self.path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get all the schedules along with when they next run
+    pub async fn list(&self) -> Vec<Entry> {
+        let now = calendar::timestamp(SystemTime::now());
+        let schedules = self.schedules.lock().await;
+        schedules
+            .iter()
+            .map(|(name, schedule)| Entry {
+                name: name.clone(),
+                schedule: schedule.clone(),
+                next: schedule
+                    .enabled
+                    .then(|| {
+                        schedule
+                            .trigger
+                            .next(now, self.location.as_ref(), &self.timezone)
+                    })
+                    .flatten()
+                    .map(calendar::system_time),
+            })
+            .collect()
+    }
+
+    /// Whether schedules can run relative to sunrise and sunset
+    pub fn has_location(&self) -> bool {
+        self.location.is_some()
+    }
+
+    /// Stop running schedules
+    pub fn shutdown(&self) {
+        self.shutdown.notify_one();
+    }
+}
+
+/// Runs the schedules which match at the start of each minute. When the clock jumps forward,
+/// any minutes that were skipped over don't get run.
+async fn runner(scheduler: Scheduler, pixels: Pixels, animator: SharedAnimator, presets: Presets) {
+    info!("scheduler started");
+    let mut last = calendar::timestamp(SystemTime::now()).div_euclid(60);
+
+    loop {
+        let since = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let wait =
+            Duration::from_secs(60) - Duration::from_millis(since.as_millis() as u64 % 60_000);
+
+        tokio::select! {
+            _ = scheduler.shutdown.notified() => break,
+            _ = time::sleep(wait) => {}
+        }
+
+        // Only run each minute once, even if the timer fires early
+        let minute = calendar::timestamp(SystemTime::now()).div_euclid(60);
+        if minute == last {
+            continue;
+        }
+        last = minute;
+
+        let due = due(
+            &*scheduler.schedules.lock().await,
+            minute * 60,
+            scheduler.location.as_ref(),
+            &scheduler.timezone,
+        );
+        for (name, action) in due {
+            info!(%name, ?action, "running schedule");
+            if let Err(err) = action.run(&pixels, &animator, &presets).await {
+                warn!(%name, %err, "failed to run schedule");
+            }
+        }
+    }
+
+    info!("shutdown successfully");
+}
+
+/// The names and actions of the enabled schedules which fire at the start of a minute
+fn due(
+    schedules: &Schedules,
+    minute: i64,
+    location: Option<&Location>,
+    timezone: &TimeZone,
+) -> Vec<(String, Action)> {
+    schedules
+        .iter()
+        .filter(|(_, schedule)| {
+            schedule.enabled && schedule.trigger.matches(minute, location, timezone)
+        })
+        .map(|(name, schedule)| (name.clone(), schedule.action.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calendar::DateTime;
+
+    fn schedules(crons: &[(&str, &str)]) -> Schedules {
+        crons
+            .iter()
+            .map(|(name, cron)| {
+                let schedule = Schedule {
+                    enabled: true,
+                    trigger: Trigger::Cron(cron.parse().unwrap()),
+                    action: Action::StopAnimation { segment: None },
+                };
+                (name.to_string(), schedule)
+            })
+            .collect()
+    }
+
+    /// The times each schedule runs at over a day, checking every minute like the runner does
+    fn runs(schedules: &Schedules, start: i64, timezone: &TimeZone) -> Vec<(String, i64)> {
+        (start..start + 86_400)
+            .step_by(60)
+            .flat_map(|minute| {
+                due(schedules, minute, None, timezone)
+                    .into_iter()
+                    .map(move |(name, _)| (name, minute))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs_once_across_daylight_saving() {
+        let timezone = "EST5EDT,M3.2.0,M11.1.0".parse::<TimeZone>().unwrap();
+        let schedules = schedules(&[("skipped", "30 2 * * *"), ("repeated", "30 1 * * *")]);
+
+        // The clocks go forward at 02:00 on March 12th, 2023, so 02:30 runs at 03:30 daylight
+        // time, when `next` says it will
+        let midnight = DateTime::new(2023, 3, 12, 5, 0).timestamp();
+        let spring = runs(&schedules, midnight, &timezone);
+        assert_eq!(
+            spring,
+            [
+                (
+                    "repeated".into(),
+                    DateTime::new(2023, 3, 12, 6, 30).timestamp()
+                ),
+                (
+                    "skipped".into(),
+                    DateTime::new(2023, 3, 12, 7, 30).timestamp()
+                ),
+            ]
+        );
+        for (name, time) in spring {
+            let trigger = &schedules[&name].trigger;
+            assert_eq!(trigger.next(midnight, None, &timezone), Some(time));
+        }
+
+        // The clocks go back at 02:00 on November 5th, 2023, so 01:30 only runs the first time
+        let midnight = DateTime::new(2023, 11, 5, 4, 0).timestamp();
+        let fall = runs(&schedules, midnight, &timezone);
+        assert_eq!(
+            fall,
+            [
+                (
+                    "repeated".into(),
+                    DateTime::new(2023, 11, 5, 5, 30).timestamp()
+                ),
+                (
+                    "skipped".into(),
+                    DateTime::new(2023, 11, 5, 7, 30).timestamp()
+                ),
+            ]
+        );
+        for (name, time) in fall {
+            let trigger = &schedules[&name].trigger;
+            assert_eq!(trigger.next(midnight, None, &timezone), Some(time));
+        }
+    }
+
+    #[test]
+    fn skips_disabled() {
+        let mut schedules = schedules(&[("hourly", "0 * * * *")]);
+        let start = DateTime::new(2024, 1, 1, 0, 0).timestamp();
+        assert_eq!(runs(&schedules, start, &TimeZone::UTC).len(), 24);
+
+        schedules.get_mut("hourly").unwrap().enabled = false;
+        assert!(runs(&schedules, start, &TimeZone::UTC).is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+use super::{calendar::Date, timezone::TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How far ahead to look for the next sunrise or sunset. Polar night and polar day can last for
+/// months at a time.
+const SEARCH_DAYS: i64 = 366;
+
+/// The days from the Unix epoch to noon on January 1st, 2000
+const J2000: f64 = 10_957.5;
+
+/// The tilt of the Earth's axis in degrees
+const OBLIQUITY: f64 = 23.4397;
+
+/// The angle of the sun's center below the horizon at sunrise and sunset in degrees. This accounts
+/// for atmospheric refraction and the radius of the sun's disc.
+const HORIZON: f64 = -0.833;
+
+/// Where the strip is on Earth, in degrees
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Location {
+    /// Positive north of the equator
+    pub latitude: f64,
+    /// Positive east of the prime meridian
+    pub longitude: f64,
+}
+
+/// The moments of the day that can trigger a schedule
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Sunrise,
+    Sunset,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Sunrise => f.write_str("sunrise"),
+            Event::Sunset => f.write_str("sunset"),
+        }
+    }
+}
+
+/// A time relative to sunrise or sunset
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Sun {
+    pub event: Event,
+    /// Minutes after the event, or before it when negative
+    #[serde(default)]
+    pub offset: i32,
+}
+
+impl Sun {
+    /// When the trigger fires on a local date, as seconds since the Unix epoch. Days where the sun
+    /// never rises or never sets have no time.
+    fn on(&self, date: Date, location: &Location) -> Option<i64> {
+        let (sunrise, sunset) = events(date, location)?;
+        let time = match self.event {
+            Event::Sunrise => sunrise,
+            Event::Sunset => sunset,
+        };
+
+        // Triggers fire at the start of a minute like cron expressions
+        Some((time + self.offset as i64 * 60).div_euclid(60) * 60)
+    }
+
+    /// Find the next time the trigger fires after a point in time, as seconds since the Unix epoch
+    pub fn next(&self, after: i64, location: &Location, timezone: &TimeZone) -> Option<i64> {
+        // Start from the day before in case the offset pushes the time into the next day
+        let start = timezone.local(after).date.days() - 1;
+        (start..start + SEARCH_DAYS)
+            .filter_map(|days| self.on(Date::from_days(days), location))
+            .find(|&time| time > after)
+    }
+
+    /// Whether the trigger fires at the start of a minute
+    pub fn matches(&self, minute: i64, location: &Location, timezone: &TimeZone) -> bool {
+        self.next(minute - 1, location, timezone) == Some(minute)
+    }
+}
+
+/// Calculate the times of sunrise and sunset as seconds since the Unix epoch using the sunrise
+/// equation. The date is treated as the local solar day at the location, so the events fall on
+/// the expected day as long as the timezone roughly follows the longitude. Nothing is returned
+/// during polar day or polar night.
+fn events(date: Date, location: &Location) -> Option<(i64, i64)> {
+    // Days since J2000 at the mean solar noon for the longitude
+    let noon = date.days() as f64 - J2000 + 0.5 - location.longitude / 360.0;
+
+    let anomaly = (357.5291 + 0.98560028 * noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.0200 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    let declination = (ecliptic.sin() * OBLIQUITY.to_radians().sin()).asin();
+    let latitude = location.latitude.to_radians();
+    let hour_angle = (HORIZON.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+
+    // The sun stays above the horizon all day below -1 and below it all day above 1
+    if !(-1.0..=1.0).contains(&hour_angle) {
+        return None;
+    }
+
+    let half_day = hour_angle.acos().to_degrees() / 360.0;
+    let timestamp = |days: f64| ((days + J2000) * 86_400.0).round() as i64;
+
+    Some((timestamp(transit - half_day), timestamp(transit + half_day)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::calendar::DateTime;
+
+    const LONDON: Location = Location {
+        latitude: 51.5074,
+        longitude: -0.1278,
+    };
+    const NEW_YORK: Location = Location {
+        latitude: 40.7128,
+        longitude: -74.006,
+    };
+    const SYDNEY: Location = Location {
+        latitude: -33.8688,
+        longitude: 151.2093,
+    };
+    const TROMSO: Location = Location {
+        latitude: 69.6492,
+        longitude: 18.9553,
+    };
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date { year, month, day }
+    }
+
+    /// Check a calculated time is within 2 minutes of a reference time
+    fn assert_near(actual: i64, expected: i64) {
+        assert!(
+            (actual - expected).abs() <= 120,
+            "{} is {} seconds from {}",
+            actual,
+            actual - expected,
+            expected
+        );
+    }
+
+    #[test]
+    fn reference_times() {
+        // Reference times from the NOAA solar calculator, in UTC
+        let (sunrise, sunset) = events(date(2023, 6, 21), &LONDON).unwrap();
+        assert_near(sunrise, DateTime::new(2023, 6, 21, 3, 43).timestamp());
+        assert_near(sunset, DateTime::new(2023, 6, 21, 20, 21).timestamp());
+
+        let (sunrise, sunset) = events(date(2023, 12, 21), &NEW_YORK).unwrap();
+        assert_near(sunrise, DateTime::new(2023, 12, 21, 12, 16).timestamp());
+        assert_near(sunset, DateTime::new(2023, 12, 21, 21, 32).timestamp());
+
+        // Sunrise in Sydney is before midnight UTC
+        let (sunrise, sunset) = events(date(2024, 1, 1), &SYDNEY).unwrap();
+        assert_near(sunrise, DateTime::new(2023, 12, 31, 18, 47).timestamp());
+        assert_near(sunset, DateTime::new(2024, 1, 1, 9, 9).timestamp());
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        assert_eq!(events(date(2023, 6, 21), &TROMSO), None);
+        assert_eq!(events(date(2023, 12, 21), &TROMSO), None);
+        assert!(events(date(2023, 3, 21), &TROMSO).is_some());
+    }
+
+    #[test]
+    fn next_skips_polar_night() {
+        let sunrise = Sun {
+            event: Event::Sunrise,
+            offset: 0,
+        };
+        let after = DateTime::new(2023, 12, 21, 12, 0).timestamp();
+        let next = sunrise.next(after, &TROMSO, &TimeZone::UTC).unwrap();
+
+        // The sun first rises again in mid January
+        let day = DateTime::from_timestamp(next).date;
+        assert_eq!((day.year, day.month), (2024, 1));
+        assert!((10..=20).contains(&day.day), "{:?}", day);
+    }
+
+    #[test]
+    fn offsets() {
+        let sunset = Sun {
+            event: Event::Sunset,
+            offset: -30,
+        };
+        let day = date(2023, 6, 21);
+        let (_, event) = events(day, &LONDON).unwrap();
+        let time = sunset.on(day, &LONDON).unwrap();
+        assert_eq!(time % 60, 0);
+        assert_eq!(time, (event - 30 * 60).div_euclid(60) * 60);
+        assert!(sunset.matches(time, &LONDON, &TimeZone::UTC));
+        assert!(!sunset.matches(time + 60, &LONDON, &TimeZone::UTC));
+    }
+}
@@ -0,0 +1,448 @@
+use super::calendar::{Date, DateTime};
+use crate::errors::InvalidTimeZone;
+use std::{iter::Peekable, str::Chars, str::FromStr};
+
+/// A timezone described by a POSIX TZ string, such as `EST5EDT,M3.2.0,M11.1.0`. This covers the
+/// offset from UTC along with the rules for daylight saving time without needing a copy of the
+/// timezone database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    /// The seconds added to UTC during standard time
+    standard: i32,
+    daylight: Option<Daylight>,
+}
+
+/// When daylight saving time is in effect
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Daylight {
+    /// The seconds added to UTC during daylight saving time
+    offset: i32,
+    start: Transition,
+    end: Transition,
+}
+
+/// A change between standard and daylight saving time, at a local time on a day of the year
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    day: RuleDay,
+    time: i32,
+}
+
+/// The forms a day of the year can take in a POSIX TZ string
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum RuleDay {
+    /// `Jn`: the day of the year 1-365, where February 29 is never counted
+    Julian(u16),
+    /// `n`: the day of the year 0-365, where February 29 is counted in leap years
+    Zero(u16),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` (5 is the last week) of month `m`
+    Month { month: u8, week: u8, weekday: u8 },
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time
+    pub const UTC: TimeZone = TimeZone {
+        standard: 0,
+        daylight: None,
+    };
+
+    /// The seconds added to UTC at a point in time
+    fn offset(&self, utc: i64) -> i32 {
+        let daylight = match &self.daylight {
+            Some(daylight) => daylight,
+            None => return self.standard,
+        };
+
+        let year = DateTime::from_timestamp(utc + self.standard as i64)
+            .date
+            .year;
+        let start = daylight.start.timestamp(year) - self.standard as i64;
+        let end = daylight.end.timestamp(year) - daylight.offset as i64;
+
+        // In the southern hemisphere, daylight saving time spans the new year
+        let active = if start < end {
+            (start..end).contains(&utc)
+        } else {
+            !(end..start).contains(&utc)
+        };
+
+        if active {
+            daylight.offset
+        } else {
+            self.standard
+        }
+    }
+
+    /// Convert a number of seconds since the Unix epoch to the local date and time
+    pub fn local(&self, utc: i64) -> DateTime {
+        DateTime::from_timestamp(utc + self.offset(utc) as i64)
+    }
+
+    /// Convert a local date and time to seconds since the Unix epoch. Times which occur twice when
+    /// the clocks go back resolve to the first occurrence, while times which are skipped when the
+    /// clocks go forward resolve to the equivalent standard time.
+    pub fn utc(&self, local: DateTime) -> i64 {
+        let local = local.timestamp();
+        let standard = local - self.standard as i64;
+
+        self.daylight
+            .iter()
+            .map(|daylight| local - daylight.offset as i64)
+            .chain([standard])
+            .filter(|&candidate| candidate + self.offset(candidate) as i64 == local)
+            .min()
+            .unwrap_or(standard)
+    }
+}
+
+impl Transition {
+    /// The local time of the transition in a year, as seconds since the Unix epoch
+    fn timestamp(&self, year: i32) -> i64 {
+        self.day.date(year).days() * 86_400 + self.time as i64
+    }
+}
+
+impl RuleDay {
+    /// The date the rule falls on in a year
+    fn date(&self, year: i32) -> Date {
+        let january = Date {
+            year,
+            month: 1,
+            day: 1,
+        };
+
+        match *self {
+            RuleDay::Julian(day) => {
+                let leap = Date::days_in_month(year, 2) == 29 && day >= 60;
+                Date::from_days(january.days() + day as i64 - 1 + leap as i64)
+            }
+            RuleDay::Zero(day) => Date::from_days(january.days() + day as i64),
+            RuleDay::Month {
+                month,
+                week,
+                weekday,
+            } => {
+                let first = Date {
+                    year,
+                    month,
+                    day: 1,
+                };
+                let mut day = 1 + (weekday + 7 - first.weekday()) % 7 + (week - 1) * 7;
+                while day > Date::days_in_month(year, month) {
+                    day -= 7;
+                }
+
+                Date { day, ..first }
+            }
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = InvalidTimeZone;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+
+        name(&mut chars)?;
+        let standard = -offset(&mut chars)?;
+        if chars.peek().is_none() {
+            return Ok(TimeZone {
+                standard,
+                daylight: None,
+            });
+        }
+
+        name(&mut chars)?;
+        let daylight = match chars.peek() {
+            Some(',') => standard + 3600,
+            _ => -offset(&mut chars)?,
+        };
+
+        expect(&mut chars, ',', "missing daylight saving time rules")?;
+        let start = transition(&mut chars)?;
+        expect(&mut chars, ',', "missing end of daylight saving time")?;
+        let end = transition(&mut chars)?;
+
+        if chars.peek().is_some() {
+            return Err(InvalidTimeZone("unexpected characters after rules"));
+        }
+
+        Ok(TimeZone {
+            standard,
+            daylight: Some(Daylight {
+                offset: daylight,
+                start,
+                end,
+            }),
+        })
+    }
+}
+
+type Cursor<'s> = Peekable<Chars<'s>>;
+
+/// Consume a character, failing if it doesn't match
+fn expect(
+    chars: &mut Cursor,
+    expected: char,
+    message: &'static str,
+) -> Result<(), InvalidTimeZone> {
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        _ => Err(InvalidTimeZone(message)),
+    }
+}
+
+/// Parse the abbreviation of a timezone, either as letters or quoted within angle brackets
+fn name(chars: &mut Cursor) -> Result<(), InvalidTimeZone> {
+    let length = if chars.next_if_eq(&'<').is_some() {
+        let mut length = 0;
+        while chars.next_if(|&c| c != '>').is_some() {
+            length += 1;
+        }
+        expect(chars, '>', "unterminated timezone name")?;
+        length
+    } else {
+        let mut length = 0;
+        while chars.next_if(|c| c.is_ascii_alphabetic()).is_some() {
+            length += 1;
+        }
+        length
+    };
+
+    if length < 3 {
+        Err(InvalidTimeZone(
+            "timezone names must be at least 3 characters",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse an unsigned number
+fn number(chars: &mut Cursor) -> Result<i32, InvalidTimeZone> {
+    let mut value: Option<i32> = None;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        chars.next();
+        value = Some(
+            value
+                .unwrap_or(0)
+                .saturating_mul(10)
+                .saturating_add(digit as i32),
+        );
+    }
+
+    value.ok_or(InvalidTimeZone("expected a number"))
+}
+
+/// Parse a time of the form `[+-]hh[:mm[:ss]]` into seconds
+fn time(chars: &mut Cursor) -> Result<i32, InvalidTimeZone> {
+    let sign = match chars.next_if(|&c| c == '+' || c == '-') {
+        Some('-') => -1,
+        _ => 1,
+    };
+
+    let mut seconds = number(chars)?.saturating_mul(3600);
+    for scale in [60, 1] {
+        if chars.next_if_eq(&':').is_none() {
+            break;
+        }
+        seconds = seconds.saturating_add(number(chars)?.saturating_mul(scale));
+    }
+
+    Ok(sign * seconds)
+}
+
+/// Parse an offset from UTC, which is positive west of the prime meridian
+fn offset(chars: &mut Cursor) -> Result<i32, InvalidTimeZone> {
+    let offset = time(chars)?;
+    if offset.abs() > 24 * 3600 {
+        Err(InvalidTimeZone("offset must be within 24 hours"))
+    } else {
+        Ok(offset)
+    }
+}
+
+/// Parse a transition of the form `date[/time]`
+fn transition(chars: &mut Cursor) -> Result<Transition, InvalidTimeZone> {
+    let day = match chars.peek() {
+        Some('J') => {
+            chars.next();
+            match number(chars)? {
+                day @ 1..=365 => RuleDay::Julian(day as u16),
+                _ => return Err(InvalidTimeZone("julian day must be between 1 and 365")),
+            }
+        }
+        Some('M') => {
+            chars.next();
+            let month = number(chars)?;
+            expect(chars, '.', "expected a week")?;
+            let week = number(chars)?;
+            expect(chars, '.', "expected a day of the week")?;
+            let weekday = number(chars)?;
+
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || !(0..=6).contains(&weekday)
+            {
+                return Err(InvalidTimeZone("invalid month, week, or day of the week"));
+            }
+            RuleDay::Month {
+                month: month as u8,
+                week: week as u8,
+                weekday: weekday as u8,
+            }
+        }
+        _ => match number(chars)? {
+            day @ 0..=365 => RuleDay::Zero(day as u16),
+            _ => return Err(InvalidTimeZone("day of the year must be between 0 and 365")),
+        },
+    };
+
+    let time = match chars.next_if_eq(&'/') {
+        Some(_) => time(chars)?,
+        None => 2 * 3600,
+    };
+
+    Ok(Transition { day, time })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fixed_offsets() {
+        let zone = "UTC0".parse::<TimeZone>().unwrap();
+        assert_eq!(zone, TimeZone::UTC);
+
+        let zone = "<+0330>-3:30".parse::<TimeZone>().unwrap();
+        assert_eq!(zone.offset(0), 3 * 3600 + 30 * 60);
+
+        let zone = "JST-9".parse::<TimeZone>().unwrap();
+        assert_eq!(zone.local(0).hour(), 9);
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for invalid in [
+            "",
+            "U0",
+            "EST",
+            "EST5EDT",
+            "EST5EDT,M3.2.0",
+            "EST5EDT,M13.2.0,M11.1.0",
+            "EST5EDT,M3.6.0,M11.1.0",
+            "EST5EDT,J0,J100",
+            "EST25",
+            "<EST5",
+            "EST5EDT,M3.2.0,M11.1.0x",
+        ] {
+            assert!(invalid.parse::<TimeZone>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn rule_dates() {
+        let second_sunday = RuleDay::Month {
+            month: 3,
+            week: 2,
+            weekday: 0,
+        };
+        assert_eq!(
+            second_sunday.date(2023),
+            Date {
+                year: 2023,
+                month: 3,
+                day: 12
+            }
+        );
+
+        let last_sunday = RuleDay::Month {
+            month: 10,
+            week: 5,
+            weekday: 0,
+        };
+        assert_eq!(
+            last_sunday.date(2023),
+            Date {
+                year: 2023,
+                month: 10,
+                day: 29
+            }
+        );
+
+        // Julian days never count February 29th, while zero-based days do
+        let march = Date {
+            year: 2024,
+            month: 3,
+            day: 1,
+        };
+        assert_eq!(RuleDay::Julian(60).date(2024), march);
+        assert_eq!(RuleDay::Zero(60).date(2024), march);
+        assert_eq!(RuleDay::Zero(59).date(2024).day, 29);
+    }
+
+    #[test]
+    fn northern_daylight_saving() {
+        let zone = "EST5EDT,M3.2.0,M11.1.0".parse::<TimeZone>().unwrap();
+
+        // Clocks go forward at 02:00 standard time and back at 02:00 daylight time
+        assert_eq!(
+            zone.offset(DateTime::new(2023, 3, 12, 6, 59).timestamp()),
+            -5 * 3600
+        );
+        assert_eq!(
+            zone.offset(DateTime::new(2023, 3, 12, 7, 0).timestamp()),
+            -4 * 3600
+        );
+        assert_eq!(
+            zone.offset(DateTime::new(2023, 11, 5, 5, 59).timestamp()),
+            -4 * 3600
+        );
+        assert_eq!(
+            zone.offset(DateTime::new(2023, 11, 5, 6, 0).timestamp()),
+            -5 * 3600
+        );
+    }
+
+    #[test]
+    fn southern_daylight_saving() {
+        let zone = "AEST-10AEDT,M10.1.0,M4.1.0/3".parse::<TimeZone>().unwrap();
+
+        // Daylight saving time spans the new year
+        assert_eq!(
+            zone.offset(DateTime::new(2024, 1, 1, 0, 0).timestamp()),
+            11 * 3600
+        );
+        assert_eq!(
+            zone.offset(DateTime::new(2024, 7, 1, 0, 0).timestamp()),
+            10 * 3600
+        );
+
+        // Clocks go back at 03:00 daylight time on April 7th, 2024
+        assert_eq!(
+            zone.offset(DateTime::new(2024, 4, 6, 15, 59).timestamp()),
+            11 * 3600
+        );
+        assert_eq!(
+            zone.offset(DateTime::new(2024, 4, 6, 16, 0).timestamp()),
+            10 * 3600
+        );
+    }
+
+    #[test]
+    fn local_round_trips() {
+        let zone = "EST5EDT,M3.2.0,M11.1.0".parse::<TimeZone>().unwrap();
+        let start = DateTime::new(2023, 1, 1, 0, 0).timestamp();
+        let end = DateTime::new(2024, 1, 1, 0, 0).timestamp();
+        for utc in (start..end).step_by(1800) {
+            let local = zone.local(utc);
+            let back = zone.utc(local);
+
+            // Only times repeated when the clocks go back resolve to a different instant
+            if back != utc {
+                assert_eq!(back, utc - 3600);
+                assert_eq!(zone.local(back), local);
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{animations::SharedAnimator, pixels::Pixels, power};
+use std::time::{Duration, SystemTime};
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        watch,
+    },
+    task::{self, JoinHandle},
+    time::{self, Instant},
+};
+use tracing::{error, info, info_span, instrument, Instrument};
+
+/// How long it takes to fade back in when a timer is cancelled while dimming
+const CANCEL_FADE: Duration = Duration::from_secs(1);
+
+/// When the strip should turn off and how long it dims for beforehand
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timer {
+    pub deadline: SystemTime,
+    pub fade: Duration,
+}
+
+impl Timer {
+    /// How long until the strip turns off
+    pub fn remaining(&self) -> Duration {
+        self.deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+
+    /// When the strip should turn off
+    fn deadline(&self) -> Instant {
+        Instant::now() + self.remaining()
+    }
+
+    /// When the strip should start dimming
+    fn fade_start(&self) -> Instant {
+        Instant::now() + self.remaining().saturating_sub(self.fade)
+    }
+}
+
+/// The action for the sleep timer to perform
+#[derive(Debug)]
+enum Action {
+    /// Replace the current timer
+    Set(Timer),
+    /// Remove the current timer, restoring the strip if it was dimming
+    Cancel,
+    /// Shutdown the sleep timer
+    Shutdown,
+}
+
+/// Dims and turns off the strip at a point in the future
+#[derive(Clone, Debug)]
+pub struct SleepTimer {
+    tx: Sender<Action>,
+    timer: watch::Receiver<Option<Timer>>,
+}
+
+impl SleepTimer {
+    /// Create and start a new sleep timer
+    pub fn new(pixels: Pixels, animator: SharedAnimator) -> (SleepTimer, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(5);
+        let (timer_tx, timer) = watch::channel(None);
+        let handle = task::spawn(
+            runner(pixels, animator, timer_tx, rx).instrument(info_span!("sleep_timer")),
+        );
+
+        (SleepTimer { tx, timer }, handle)
+    }
+
+    /// Send an action to the timer
+    async fn send(&self, action: Action) {
+        if let Err(err) = self.tx.send(action).await {
+            error!(%err, "failed to send action");
+        }
+    }
+
+    /// Turn off the strip after a duration, dimming it over the end of that duration. Any
+    /// existing timer is replaced.
+    #[instrument(skip(self))]
+    pub async fn set(&self, duration: Duration, fade: Duration) {
+        self.restore(Timer {
+            deadline: SystemTime::now() + duration,
+            fade,
+        })
+        .await
+    }
+
+    /// Replace any existing timer with one that was previously set
+    #[instrument(skip(self))]
+    pub async fn restore(&self, timer: Timer) {
+        self.send(Action::Set(timer)).await
+    }
+
+    /// Remove the current timer
+    #[instrument(skip(self))]
+    pub async fn cancel(&self) {
+        self.send(Action::Cancel).await
+    }
+
+    /// Get the currently running timer
+    pub fn current(&self) -> Option<Timer> {
+        *self.timer.borrow()
+    }
+
+    /// Watch for changes to the timer
+    pub fn watch(&self) -> watch::Receiver<Option<Timer>> {
+        self.timer.clone()
+    }
+
+    /// Shutdown the timer
+    #[instrument(skip(self))]
+    pub async fn shutdown(&self) {
+        self.send(Action::Shutdown).await
+    }
+}
+
+/// Waits for the current timer to expire, dimming the strip as it approaches
+async fn runner(
+    pixels: Pixels,
+    animator: SharedAnimator,
+    timer: watch::Sender<Option<Timer>>,
+    mut actions: Receiver<Action>,
+) {
+    info!("sleep timer started");
+
+    // The power generation the strip was left in when it started dimming
+    let mut dimmed = None;
+
+    loop {
+        let current = *timer.borrow();
+        let action = match current {
+            None => actions.recv().await,
+            Some(current) => {
+                let wake = if dimmed.is_some() {
+                    current.deadline()
+                } else {
+                    current.fade_start()
+                };
+
+                tokio::select! {
+                    action = actions.recv() => action,
+                    _ = time::sleep_until(wake) => {
+                        if dimmed.is_some() {
+                            // Turn everything off once the fade is complete
+                            animator.stop_all().await;
+                            power::off(&pixels, &animator, Duration::ZERO).await;
+                            timer.send_replace(None);
+                            dimmed = None;
+                            info!("sleep timer expired");
+                        } else {
+                            dimmed = Some(pixels.dim(current.remaining()));
+                            pixels.show();
+                            info!("dimming strip");
+                        }
+
+                        continue;
+                    }
+                }
+            }
+        };
+
+        // Undo any dimming when the timer changes, unless the strip has been switched on or off
+        // since it started
+        if let Some(generation) = dimmed.take() {
+            let changed = pixels.power_generation() != generation;
+            if !changed && !matches!(action, Some(Action::Shutdown) | None) {
+                pixels.power_on(CANCEL_FADE);
+                pixels.show();
+            }
+        }
+
+        match action {
+            Some(Action::Set(new)) => {
+                timer.send_replace(Some(new));
+            }
+            Some(Action::Cancel) => {
+                timer.send_replace(None);
+            }
+            Some(Action::Shutdown) | None => break,
+        }
+    }
+
+    info!("shutdown successfully");
+}
@@ -0,0 +1,267 @@
+use crate::{
+    animations::{Animator, RunningAnimations},
+    config::{Config, Startup},
+    errors::StateError,
+    pixels::{Color, Pixels, Snapshot},
+    power,
+    sleep::{SleepTimer, Timer},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::{
+    fs,
+    sync::watch,
+    task::{self, JoinHandle},
+    time,
+};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
+
+/// How long to wait for changes to settle before saving them
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// What the strip was showing, saved so it can be restored after a restart
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    on: bool,
+    brightness: u8,
+    animation: Option<String>,
+    pixels: Vec<[u16; 4]>,
+    sleep: Option<SleepState>,
+    segments: BTreeMap<String, SegmentState>,
+}
+
+/// A sleep timer which was running
+#[derive(Debug, Deserialize, Serialize)]
+struct SleepState {
+    /// When the strip turns off in seconds since the Unix epoch
+    deadline: u64,
+    /// How long the strip dims for beforehand in seconds
+    fade: u64,
+}
+
+/// What a segment was showing
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+struct SegmentState {
+    brightness: u8,
+    animation: Option<String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            on: true,
+            brightness: u8::MAX,
+            animation: None,
+            pixels: Vec::new(),
+            sleep: None,
+            segments: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for SegmentState {
+    fn default() -> Self {
+        SegmentState {
+            brightness: u8::MAX,
+            animation: None,
+        }
+    }
+}
+
+impl State {
+    /// Combine the contents of the strip with the animations and timer running on it
+    fn capture(snapshot: &Snapshot, running: &RunningAnimations, timer: Option<Timer>) -> Self {
+        let segments = snapshot
+            .segments
+            .iter()
+            .map(|(name, &brightness)| {
+                let animation = running.get(&Some(name.clone())).cloned();
+                let state = SegmentState {
+                    brightness,
+                    animation,
+                };
+                (name.clone(), state)
+            })
+            .collect();
+
+        State {
+            on: snapshot.on,
+            brightness: snapshot.brightness,
+            animation: running.get(&None).cloned(),
+            pixels: snapshot.pixels.clone(),
+            sleep: timer.map(|timer| SleepState {
+                deadline: timer
+                    .deadline
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                fade: timer.fade.as_secs(),
+            }),
+            segments,
+        }
+    }
+
+    /// Show the state on the strip and start its animations again. If the strip was turned off,
+    /// it stays off with its animations paused until it is turned back on. A sleep timer which
+    /// expired while the controller was stopped turns the strip off right away.
+    async fn apply(self, pixels: &Pixels, animator: &Animator, sleep_timer: &SleepTimer) {
+        if !self.on {
+            power::off(pixels, animator, Duration::ZERO).await;
+        }
+
+        pixels.restore(
+            Snapshot {
+                on: self.on,
+                pixels: self.pixels,
+                brightness: self.brightness,
+                segments: self
+                    .segments
+                    .iter()
+                    .map(|(name, state)| (name.clone(), state.brightness))
+                    .collect(),
+            },
+            Duration::ZERO,
+        );
+        pixels.show();
+
+        let animations = self
+            .segments
+            .into_iter()
+            .filter_map(|(name, state)| Some((Some(name), state.animation?)))
+            .chain(self.animation.map(|id| (None, id)));
+        for (segment, id) in animations {
+            if let Err(err) = animator.start(segment.as_deref(), &id).await {
+                warn!(%err, %id, "could not restore animation");
+            }
+        }
+
+        if let Some(sleep) = self.sleep {
+            let timer = Timer {
+                deadline: UNIX_EPOCH + Duration::from_secs(sleep.deadline),
+                fade: Duration::from_secs(sleep.fade),
+            };
+            sleep_timer.restore(timer).await;
+        }
+    }
+}
+
+/// Load a value saved to a file, if it exists
+#[instrument]
+pub async fn load<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, StateError> {
+    match fs::read(path).await {
+        Ok(contents) => Ok(Some(toml::from_slice(&contents)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Save a value to a file. The value is written to a temporary file first so that a crash while
+/// saving never leaves a partially written file behind.
+#[instrument(skip(value))]
+pub async fn save<T: Serialize>(value: &T, path: &Path) -> Result<(), StateError> {
+    let serialized = toml::to_string(value)?;
+    let temporary = path.with_extension("toml.tmp");
+    fs::write(&temporary, serialized).await?;
+    fs::rename(&temporary, path).await?;
+
+    debug!("saved to file");
+    Ok(())
+}
+
+/// Set up the strip according to the startup mode from the configuration. When restoring, the
+/// state of the strip is saved whenever it changes by the returned task, which exits once the
+/// pixel manager and animator shut down.
+#[instrument(skip_all)]
+pub async fn startup(
+    config: &Config,
+    pixels: &Pixels,
+    animator: &Animator,
+    sleep_timer: &SleepTimer,
+) -> Option<JoinHandle<()>> {
+    match &config.startup {
+        Startup::Restore => {
+            let path = config.state_path();
+            match load::<State>(&path).await {
+                Ok(Some(state)) => {
+                    state.apply(pixels, animator, sleep_timer).await;
+                    info!("restored previous state");
+                }
+                Ok(None) => info!("no previous state to restore"),
+                Err(err) => error!(%err, "failed to load previous state"),
+            }
+
+            let persister = persister(
+                path,
+                pixels.snapshots(),
+                animator.running(),
+                sleep_timer.watch(),
+            );
+            Some(task::spawn(persister.instrument(info_span!("persister"))))
+        }
+        Startup::Off => None,
+        Startup::Fixed(fixed) => {
+            let c = fixed.color;
+            pixels.fill(match c.w {
+                Some(w) => Color::rgbw(c.r, c.g, c.b, w),
+                None => Color::rgb(c.r, c.g, c.b),
+            });
+            pixels.brightness(fixed.brightness);
+            pixels.show();
+
+            if let Some(id) = &fixed.animation {
+                if let Err(err) = animator.start(None, id).await {
+                    warn!(%err, %id, "could not start animation");
+                }
+            }
+
+            None
+        }
+    }
+}
+
+/// Saves the state of the strip whenever it changes. Bursts of changes are combined into a single
+/// write, and the latest state is always saved before exiting.
+async fn persister(
+    path: PathBuf,
+    mut snapshots: watch::Receiver<Snapshot>,
+    mut running: watch::Receiver<RunningAnimations>,
+    mut timer: watch::Receiver<Option<Timer>>,
+) {
+    info!("persister started");
+
+    loop {
+        let closed = tokio::select! {
+            result = snapshots.changed() => result.is_err(),
+            result = running.changed() => result.is_err(),
+            result = timer.changed() => result.is_err(),
+        };
+        if closed {
+            break;
+        }
+
+        time::sleep(DEBOUNCE).await;
+
+        let state = State::capture(
+            &snapshots.borrow_and_update(),
+            &running.borrow_and_update(),
+            *timer.borrow_and_update(),
+        );
+        if let Err(err) = save(&state, &path).await {
+            error!(%err, "failed to save state");
+        }
+    }
+
+    let state = State::capture(&snapshots.borrow(), &running.borrow(), *timer.borrow());
+    if let Err(err) = save(&state, &path).await {
+        error!(%err, "failed to save state");
+    }
+
+    info!("shutdown successfully")
+}
@@ -3,18 +3,24 @@ use std::time::Duration;
 mod wrapper;
 use wrapper::*;
 
-const LEDS: u16 = 150;
+/// Describes the animation to the controller, which reads it from the `lights` custom section
+#[link_section = "lights"]
+#[used]
+static METADATA: [u8; include_bytes!("../lights.toml").len()] = *include_bytes!("../lights.toml");
+
 static mut INDEX: u8 = 0;
 
 #[no_mangle]
 pub extern "C" fn animate() {
-    for i in 0..LEDS {
-        let pixel_index = unsafe { (i * 256 / LEDS) + INDEX as u16 };
+    let leds = led_count() as u32;
+    for i in 0..leds {
+        let pixel_index = unsafe { (i * 256 / leds) + INDEX as u32 };
         let (r, g, b) = wheel((pixel_index & 255) as u8);
-        set(i, r, g, b);
+        set(i as u16, r, g, b);
     }
 
-    unsafe { INDEX += 1 }
+    let speed = param_f64("speed") as u8;
+    unsafe { INDEX = INDEX.wrapping_add(speed) }
 
     show();
     sleep(Duration::from_millis(1));
@@ -3,23 +3,28 @@ use std::time::Duration;
 mod wrapper;
 use wrapper::*;
 
-const LEDS: u8 = 150;
-static mut INDEX: u8 = 0;
+/// Describes the animation to the controller, which reads it from the `lights` custom section
+#[link_section = "lights"]
+#[used]
+static METADATA: [u8; include_bytes!("../lights.toml").len()] = *include_bytes!("../lights.toml");
+
+static mut INDEX: u16 = 0;
 
 #[no_mangle]
 pub extern "C" fn animate() {
     fill(0, 0, 0);
 
     unsafe {
-        if INDEX >= LEDS - 1 {
+        if INDEX >= led_count().saturating_sub(1) {
             INDEX = 0;
         } else {
             INDEX += 1;
         }
 
-        set(INDEX as u16, 255, 0, 0);
+        let (r, g, b) = param_color("color");
+        set(INDEX, r, g, b);
     }
 
     show();
-    sleep(Duration::from_millis(1));
+    sleep(Duration::from_secs_f64(param_f64("delay") / 1000.0));
 }
@@ -6,6 +6,9 @@ mod ffi {
         pub(super) fn set(index: i32, r: i32, g: i32, b: i32);
         pub(super) fn show();
         pub(super) fn sleep(secs: f64);
+        pub(super) fn param_f64(name: *const u8, length: usize) -> f64;
+        pub(super) fn led_count() -> u32;
+        pub(super) fn param_color(name: *const u8, length: usize) -> u32;
     }
 }
 
@@ -29,3 +32,16 @@ pub fn sleep(duration: Duration) {
     let secs = duration.as_secs_f64();
     unsafe { ffi::sleep(secs) }
 }
+
+pub fn param_f64(name: &str) -> f64 {
+    unsafe { ffi::param_f64(name.as_ptr(), name.len()) }
+}
+
+pub fn param_color(name: &str) -> (u8, u8, u8) {
+    let [_, r, g, b] = unsafe { ffi::param_color(name.as_ptr(), name.len()) }.to_be_bytes();
+    (r, g, b)
+}
+
+pub fn led_count() -> u16 {
+    unsafe { ffi::led_count() as u16 }
+}